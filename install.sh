@@ -30,21 +30,9 @@ if ! command -v cargo &> /dev/null; then
 fi
 echo -e "  ${GREEN}✓${NC} Rust/Cargo found: $(cargo --version)"
 
-# Check for jq (required by statusline.sh)
-if ! command -v jq &> /dev/null; then
-    echo -e "${RED}Error: jq not found.${NC}"
-    echo -e "Please install jq first:"
-    echo -e "  macOS: ${GREEN}brew install jq${NC}"
-    echo -e "  Linux: ${GREEN}sudo apt install jq${NC}"
-    exit 1
-fi
-echo -e "  ${GREEN}✓${NC} jq found: $(jq --version)"
-
-# Check for git (for git status in statusline)
-if ! command -v git &> /dev/null; then
-    echo -e "${YELLOW}Warning: git not found. Git status will not be displayed.${NC}"
-else
-    echo -e "  ${GREEN}✓${NC} git found: $(git --version | head -1)"
+# jq is only needed here, to merge into an existing settings.json
+if command -v jq &> /dev/null; then
+    echo -e "  ${GREEN}✓${NC} jq found: $(jq --version)"
 fi
 
 echo ""
@@ -73,28 +61,28 @@ cp "$SCRIPT_DIR/target/release/claude-status" "$BIN_DIR/"
 chmod +x "$BIN_DIR/claude-status"
 echo -e "  ${GREEN}✓${NC} Installed binary to $BIN_DIR/claude-status"
 
-# Make statusline.sh executable (referenced from repo, not copied)
-chmod +x "$SCRIPT_DIR/statusline.sh"
-echo -e "  ${GREEN}✓${NC} statusline.sh ready at $SCRIPT_DIR/"
-
 # Configure Claude Code settings
 echo ""
 echo -e "${YELLOW}Configuring Claude Code...${NC}"
 
 SETTINGS_FILE="$CLAUDE_DIR/settings.json"
+STATUS_CMD="$BIN_DIR/claude-status render"
 
 if [ -f "$SETTINGS_FILE" ]; then
     # Check if statusLine is already configured
     if grep -q '"statusLine"' "$SETTINGS_FILE"; then
         echo -e "  ${YELLOW}!${NC} statusLine already configured in settings.json"
-        echo -e "    Please verify it points to: ${GREEN}$SCRIPT_DIR/statusline.sh${NC}"
+        echo -e "    Please verify its command is: ${GREEN}$STATUS_CMD${NC}"
+    elif ! command -v jq &> /dev/null; then
+        echo -e "  ${YELLOW}!${NC} jq not found - please add this to settings.json manually:"
+        echo -e "    ${GREEN}\"statusLine\": {\"type\": \"command\", \"command\": \"$STATUS_CMD\", \"padding\": 0}${NC}"
     else
         # Backup existing settings
         cp "$SETTINGS_FILE" "$SETTINGS_FILE.backup"
         echo -e "  ${GREEN}✓${NC} Backed up settings to $SETTINGS_FILE.backup"
 
         # Add statusLine configuration using jq
-        jq --arg cmd "$SCRIPT_DIR/statusline.sh" '. + {"statusLine": {"type": "command", "command": $cmd, "padding": 0}}' \
+        jq --arg cmd "$STATUS_CMD" '. + {"statusLine": {"type": "command", "command": $cmd, "padding": 0}}' \
             "$SETTINGS_FILE" > "$SETTINGS_FILE.tmp" && mv "$SETTINGS_FILE.tmp" "$SETTINGS_FILE"
         echo -e "  ${GREEN}✓${NC} Added statusLine configuration to settings.json"
     fi
@@ -104,7 +92,7 @@ else
 {
   "statusLine": {
     "type": "command",
-    "command": "$SCRIPT_DIR/statusline.sh",
+    "command": "$STATUS_CMD",
     "padding": 0
   }
 }
@@ -122,10 +110,10 @@ else
     echo -e "  ${RED}✗${NC} Binary not executable"
 fi
 
-if [ -x "$SCRIPT_DIR/statusline.sh" ]; then
-    echo -e "  ${GREEN}✓${NC} statusline.sh is executable"
+if echo '{}' | "$BIN_DIR/claude-status" render > /dev/null; then
+    echo -e "  ${GREEN}✓${NC} Render mode works"
 else
-    echo -e "  ${RED}✗${NC} statusline.sh not executable"
+    echo -e "  ${RED}✗${NC} Render mode failed"
 fi
 
 # Success message
@@ -0,0 +1,139 @@
+use crate::transcript::TranscriptParser;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Checkpoint Persistence
+// ============================================================================
+
+/// Bump whenever the serialized parser layout changes so stale caches are
+/// discarded instead of half-deserialized.
+const CHECKPOINT_VERSION: u32 = 14;
+
+/// Number of bytes before the checkpoint offset used to detect a file that
+/// was replaced in place (same inode, different content).
+const FINGERPRINT_LEN: u64 = 64;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    version: u32,
+    path: String,
+    file_id: Option<(u64, u64)>,
+    pub offset: u64,
+    fingerprint: u64,
+    pub parser: TranscriptParser,
+}
+
+/// Resolve the cache directory: `CLAUDE_STATUS_CACHE_DIR`, then
+/// `$XDG_CACHE_HOME/claude-status`, then `~/.cache/claude-status`.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("CLAUDE_STATUS_CACHE_DIR").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir).join("claude-status"));
+    }
+    env::var_os("HOME")
+        .filter(|v| !v.is_empty())
+        .map(|home| PathBuf::from(home).join(".cache").join("claude-status"))
+}
+
+/// Load the checkpoint for `path` if it still describes `file`.
+///
+/// Returns `None` (forcing a full parse) when the cache is missing, corrupt,
+/// from another version, or the file was truncated or rotated since.
+pub fn load(cache_dir: &Path, path: &Path, file: &mut File) -> Option<Checkpoint> {
+    let data = fs::read(checkpoint_path(cache_dir, path)).ok()?;
+    let cp: Checkpoint = serde_json::from_slice(&data).ok()?;
+
+    if cp.version != CHECKPOINT_VERSION || cp.path != path_key(path) {
+        return None;
+    }
+
+    let meta = file.metadata().ok()?;
+    if file_id(&meta) != cp.file_id || meta.len() < cp.offset {
+        return None;
+    }
+    if fingerprint(file, cp.offset)? != cp.fingerprint {
+        return None;
+    }
+
+    Some(cp)
+}
+
+/// Persist the parser state at `offset`. Failures are ignored: the cache is
+/// only an optimization.
+pub fn save(cache_dir: &Path, path: &Path, file: &mut File, offset: u64, parser: &TranscriptParser) {
+    let Some(fingerprint) = fingerprint(file, offset) else {
+        return;
+    };
+    let file_id = file.metadata().ok().and_then(|m| file_id(&m));
+
+    let cp = Checkpoint {
+        version: CHECKPOINT_VERSION,
+        path: path_key(path),
+        file_id,
+        offset,
+        fingerprint,
+        parser: parser.clone(),
+    };
+
+    let Ok(data) = serde_json::to_vec(&cp) else {
+        return;
+    };
+    if fs::create_dir_all(cache_dir).is_err() {
+        return;
+    }
+
+    // Write-then-rename so a concurrent reader never sees a partial file
+    let target = checkpoint_path(cache_dir, path);
+    let tmp = target.with_extension(format!("tmp.{}", std::process::id()));
+    if fs::write(&tmp, data).is_ok() && fs::rename(&tmp, &target).is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+fn path_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn checkpoint_path(cache_dir: &Path, path: &Path) -> PathBuf {
+    cache_dir.join(format!("{:016x}.json", fnv1a(path_key(path).as_bytes())))
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Hash of the bytes just before `offset`.
+fn fingerprint(file: &mut File, offset: u64) -> Option<u64> {
+    let start = offset.saturating_sub(FINGERPRINT_LEN);
+    let mut buf = vec![0u8; (offset - start) as usize];
+    file.seek(SeekFrom::Start(start)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    Some(fnv1a(&buf))
+}
+
+/// FNV-1a, chosen over `DefaultHasher` because its output is stable across
+/// Rust releases and cache file names must survive upgrades.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
@@ -0,0 +1,596 @@
+use crate::icons::IconSet;
+use crate::pricing::Price;
+use crate::theme::{self, Color, ColorMode, Palette};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// User Configuration (~/.claude/statusline.toml)
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub segments: Segments,
+    pub theme: ThemeConfig,
+    /// User-defined themes, selectable by name in `theme.name`
+    pub themes: HashMap<String, CustomTheme>,
+    pub icons: IconsConfig,
+    /// Price overrides by model id prefix, on top of the built-in table
+    pub pricing: HashMap<String, Price>,
+    pub mcp: McpConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpConfig {
+    /// Display settings by MCP server name (the `github` in
+    /// `mcp__github__create_issue`)
+    pub servers: HashMap<String, McpServer>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpServer {
+    /// Shown instead of the `server:` prefix
+    pub icon: Option<String>,
+    pub color: Option<Color>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconsConfig {
+    /// Overridden by `CLAUDE_STATUS_ICONS`
+    pub set: IconSet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Segment {
+    State,
+    Todos,
+    Skills,
+    Agents,
+    Jobs,
+    Tools,
+    Files,
+    Diff,
+    Tokens,
+    Cost,
+    Compaction,
+}
+
+impl Segment {
+    fn name(self) -> &'static str {
+        match self {
+            Segment::State => "state",
+            Segment::Todos => "todos",
+            Segment::Skills => "skills",
+            Segment::Agents => "agents",
+            Segment::Jobs => "jobs",
+            Segment::Tools => "tools",
+            Segment::Files => "files",
+            Segment::Diff => "diff",
+            Segment::Tokens => "tokens",
+            Segment::Cost => "cost",
+            Segment::Compaction => "compaction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Segments {
+    /// Display order. Segments left out are hidden.
+    pub order: Vec<Segment>,
+    pub state: StateSegment,
+    pub todos: TodosSegment,
+    pub skills: SkillsSegment,
+    pub agents: AgentsSegment,
+    pub jobs: JobsSegment,
+    pub tools: ToolsSegment,
+    pub files: FilesSegment,
+    pub diff: DiffSegment,
+    pub tokens: TokensSegment,
+    pub cost: CostSegment,
+    pub compaction: CompactionSegment,
+}
+
+impl Default for Segments {
+    fn default() -> Self {
+        Segments {
+            order: vec![
+                Segment::State,
+                Segment::Todos,
+                Segment::Skills,
+                Segment::Agents,
+                Segment::Jobs,
+                Segment::Tools,
+                Segment::Files,
+                Segment::Diff,
+                Segment::Tokens,
+                Segment::Cost,
+                Segment::Compaction,
+            ],
+            state: StateSegment::default(),
+            todos: TodosSegment::default(),
+            skills: SkillsSegment::default(),
+            agents: AgentsSegment::default(),
+            jobs: JobsSegment::default(),
+            tools: ToolsSegment::default(),
+            files: FilesSegment::default(),
+            diff: DiffSegment::default(),
+            tokens: TokensSegment::default(),
+            cost: CostSegment::default(),
+            compaction: CompactionSegment::default(),
+        }
+    }
+}
+
+impl Segments {
+    /// Segments to render, in order, skipping disabled ones.
+    pub fn visible(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.order.iter().copied().filter(|s| match s {
+            Segment::State => self.state.enabled,
+            Segment::Todos => self.todos.enabled,
+            Segment::Skills => self.skills.enabled,
+            Segment::Agents => self.agents.enabled,
+            Segment::Jobs => self.jobs.enabled,
+            Segment::Tools => self.tools.enabled,
+            Segment::Files => self.files.enabled,
+            Segment::Diff => self.diff.enabled,
+            Segment::Tokens => self.tokens.enabled,
+            Segment::Cost => self.cost.enabled,
+            Segment::Compaction => self.compaction.enabled,
+        })
+    }
+}
+
+/// Working, waiting for approval, interrupted, idle or API error.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateSegment {
+    pub enabled: bool,
+    /// Assume a permission prompt once a tool that needs approval has been
+    /// pending this long with no transcript activity; 0 disables
+    pub permission_after_secs: u64,
+}
+
+impl Default for StateSegment {
+    fn default() -> Self {
+        StateSegment {
+            enabled: true,
+            permission_after_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TodosSegment {
+    pub enabled: bool,
+}
+
+impl Default for TodosSegment {
+    fn default() -> Self {
+        TodosSegment { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SkillsSegment {
+    pub enabled: bool,
+    /// Most recent skills kept
+    pub max: usize,
+}
+
+impl Default for SkillsSegment {
+    fn default() -> Self {
+        SkillsSegment { enabled: true, max: 3 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentsSegment {
+    pub enabled: bool,
+    /// Most recent agents kept
+    pub max: usize,
+    /// Show what a running agent is doing, e.g. `Explore ⟳ Grep "auth"`
+    pub show_activity: bool,
+}
+
+impl Default for AgentsSegment {
+    fn default() -> Self {
+        AgentsSegment {
+            enabled: true,
+            max: 5,
+            show_activity: true,
+        }
+    }
+}
+
+/// Background shells started with `run_in_background`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsSegment {
+    pub enabled: bool,
+    /// Most recent jobs kept
+    pub max: usize,
+}
+
+impl Default for JobsSegment {
+    fn default() -> Self {
+        JobsSegment { enabled: false, max: 3 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsSegment {
+    pub enabled: bool,
+    /// Running tools tracked
+    pub max_running: usize,
+    /// Running tools displayed
+    pub show_running: usize,
+    /// Completed tools displayed
+    pub show_completed: usize,
+    /// Completed tools displayed while a file operation is running
+    pub show_completed_with_file_ops: usize,
+    /// Show completed MCP tools as one count per server
+    pub group_mcp: bool,
+    /// Show the last error message after the failing tool
+    pub show_error_message: bool,
+    /// Color a tool as slow once a call runs this long; 0 disables
+    pub slow_after_secs: u64,
+    /// Color a tool as an error once a call runs this long; 0 disables
+    pub stuck_after_secs: u64,
+}
+
+impl Default for ToolsSegment {
+    fn default() -> Self {
+        ToolsSegment {
+            enabled: true,
+            max_running: 10,
+            show_running: 2,
+            show_completed: 5,
+            show_completed_with_file_ops: 2,
+            group_mcp: false,
+            show_error_message: true,
+            slow_after_secs: 30,
+            stuck_after_secs: 120,
+        }
+    }
+}
+
+/// Files read, created, or modified this turn.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesSegment {
+    pub enabled: bool,
+    /// Name the most recently touched file after the count
+    pub show_last: bool,
+}
+
+impl Default for FilesSegment {
+    fn default() -> Self {
+        FilesSegment { enabled: false, show_last: true }
+    }
+}
+
+/// Lines added and removed by Claude's edits this turn.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiffSegment {
+    pub enabled: bool,
+    /// Show session totals after the turn's
+    pub show_session: bool,
+}
+
+impl Default for DiffSegment {
+    fn default() -> Self {
+        DiffSegment { enabled: false, show_session: true }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokensSegment {
+    pub enabled: bool,
+    /// Show session totals and cache hit ratio next to the turn's tokens
+    pub show_session: bool,
+}
+
+impl Default for TokensSegment {
+    fn default() -> Self {
+        TokensSegment { enabled: false, show_session: true }
+    }
+}
+
+/// Estimated spend from transcript usage and the price table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostSegment {
+    pub enabled: bool,
+    /// Show the session estimate after the turn's
+    pub show_session: bool,
+}
+
+impl Default for CostSegment {
+    fn default() -> Self {
+        CostSegment { enabled: false, show_session: true }
+    }
+}
+
+/// How often the context was compacted, and how long ago.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompactionSegment {
+    pub enabled: bool,
+    /// Show the time since the last compaction after the count
+    pub show_since: bool,
+}
+
+impl Default for CompactionSegment {
+    fn default() -> Self {
+        CompactionSegment { enabled: false, show_since: true }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub name: String,
+    pub colors: ColorMode,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            name: theme::DEFAULT_THEME.to_string(),
+            colors: ColorMode::Auto,
+        }
+    }
+}
+
+/// A theme defined in the config file. Unset roles come from `base`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CustomTheme {
+    pub base: Option<String>,
+    pub complete: Option<Color>,
+    pub running: Option<Color>,
+    pub error: Option<Color>,
+    pub slow: Option<Color>,
+    pub accent: Option<Color>,
+    pub separator: Option<Color>,
+    pub branch: Option<Color>,
+    pub directory: Option<Color>,
+    pub model: Option<Color>,
+}
+
+impl Config {
+    /// Copy with the per-segment caps lifted, for machine-readable output
+    /// where dropping entries would lose data.
+    pub fn unlimited(&self) -> Config {
+        let mut config = self.clone();
+        config.segments.tools.max_running = usize::MAX;
+        config.segments.agents.max = usize::MAX;
+        config.segments.jobs.max = usize::MAX;
+        config.segments.skills.max = usize::MAX;
+        config
+    }
+
+    /// Look up a theme by name, user-defined themes first.
+    pub fn palette(&self, name: &str) -> Option<Palette> {
+        let Some(custom) = self.themes.get(name) else {
+            return theme::builtin(name);
+        };
+
+        let base = theme::builtin(custom.base.as_deref().unwrap_or(theme::DEFAULT_THEME))?;
+        Some(Palette {
+            complete: custom.complete.unwrap_or(base.complete),
+            running: custom.running.unwrap_or(base.running),
+            error: custom.error.unwrap_or(base.error),
+            slow: custom.slow.unwrap_or(base.slow),
+            accent: custom.accent.unwrap_or(base.accent),
+            separator: custom.separator.unwrap_or(base.separator),
+            branch: custom.branch.unwrap_or(base.branch),
+            directory: custom.directory.unwrap_or(base.directory),
+            model: custom.model.unwrap_or(base.model),
+        })
+    }
+}
+
+// ============================================================================
+// Loading & Validation
+// ============================================================================
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Config file location: `CLAUDE_STATUS_CONFIG`, else `~/.claude/statusline.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("CLAUDE_STATUS_CONFIG").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    env::var_os("HOME")
+        .filter(|v| !v.is_empty())
+        .map(|home| PathBuf::from(home).join(".claude").join("statusline.toml"))
+}
+
+/// Load the config file and apply environment overrides. A missing file
+/// yields the defaults; an unreadable or invalid one is an error.
+pub fn load() -> Result<Config, ConfigError> {
+    let mut config = match config_path() {
+        Some(path) if path.exists() => load_from(&path)?,
+        _ => Config::default(),
+    };
+
+    if let Ok(name) = env::var("CLAUDE_STATUS_ICONS") {
+        if !name.is_empty() {
+            config.icons.set = IconSet::parse(&name).ok_or_else(|| ConfigError {
+                path: PathBuf::from("CLAUDE_STATUS_ICONS"),
+                message: format!(
+                    "unknown icon set \"{}\" (available: {})",
+                    name,
+                    IconSet::NAMES.join(", ")
+                ),
+            })?;
+        }
+    }
+
+    Ok(config)
+}
+
+/// Load and validate a config file. `.json` files are parsed as JSON,
+/// everything else as TOML.
+pub fn load_from(path: &Path) -> Result<Config, ConfigError> {
+    let error = |message: String| ConfigError {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let text = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+
+    let is_json = path.extension().and_then(|e| e.to_str()) == Some("json");
+    let config: Config = if is_json {
+        serde_json::from_str(&text).map_err(|e| error(e.to_string()))?
+    } else {
+        toml::from_str(&text).map_err(|e| error(describe_toml_error(&text, &e)))?
+    };
+
+    config.validate().map_err(error)?;
+    Ok(config)
+}
+
+/// One-line TOML error: `line L, column C: message`.
+fn describe_toml_error(text: &str, err: &toml::de::Error) -> String {
+    let Some(span) = err.span() else {
+        return err.message().to_string();
+    };
+    let before = &text[..span.start.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    format!("line {}, column {}: {}", line, column, err.message())
+}
+
+impl Config {
+    fn validate(&self) -> Result<(), String> {
+        let order = &self.segments.order;
+        for (i, segment) in order.iter().enumerate() {
+            if order[..i].contains(segment) {
+                return Err(format!(
+                    "segments.order: \"{}\" is listed more than once",
+                    segment.name()
+                ));
+            }
+        }
+
+        for (name, custom) in &self.themes {
+            if let Some(base) = &custom.base {
+                if theme::builtin(base).is_none() {
+                    return Err(format!(
+                        "themes.{}.base: unknown built-in theme \"{}\" (available: {})",
+                        name,
+                        base,
+                        builtin_names()
+                    ));
+                }
+            }
+        }
+        if self.palette(&self.theme.name).is_none() {
+            return Err(format!(
+                "theme.name: unknown theme \"{}\" (available: {})",
+                self.theme.name,
+                builtin_names()
+            ));
+        }
+
+        let tools = &self.segments.tools;
+        if tools.show_running > tools.max_running {
+            return Err(format!(
+                "segments.tools.show_running ({}) exceeds max_running ({})",
+                tools.show_running, tools.max_running
+            ));
+        }
+        if tools.slow_after_secs > 0 && tools.stuck_after_secs > 0 && tools.slow_after_secs > tools.stuck_after_secs {
+            return Err(format!(
+                "segments.tools.slow_after_secs ({}) exceeds stuck_after_secs ({})",
+                tools.slow_after_secs, tools.stuck_after_secs
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn builtin_names() -> String {
+    theme::BUILTIN_THEMES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use crate::theme::Theme;
+
+    #[test]
+    fn test_config_reports_invalid_values() {
+        let dir = temp_dir("config-invalid");
+        let path = dir.join("statusline.toml");
+
+        fs::write(&path, "[segments]\norder = [\"todos\", \"bogus\"]\n").unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.message.contains("bogus"), "{}", err);
+
+        fs::write(&path, "[segments]\norder = [\"todos\", \"todos\"]\n").unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.message.contains("more than once"), "{}", err);
+
+        fs::write(&path, "[segments.tools]\nmax_runing = 3\n").unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.message.contains("max_runing"), "{}", err);
+    }
+
+    #[test]
+    fn test_config_custom_theme() {
+        let dir = temp_dir("theme");
+        let path = dir.join("statusline.toml");
+        fs::write(
+            &path,
+            "[theme]\nname = \"mine\"\ncolors = \"truecolor\"\n\n[themes.mine]\nbase = \"nord\"\ncomplete = \"#00ff00\"\n",
+        )
+        .unwrap();
+        let config = load_from(&path).unwrap();
+        let theme = Theme::from_config(&config);
+        assert_eq!(theme.complete, "\x1b[38;2;0;255;0m");
+        assert_eq!(theme.error, "\x1b[38;2;191;97;106m");
+
+        fs::write(&path, "[themes.mine]\ncomplete = \"green\"\n").unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.message.contains("invalid color"), "{}", err);
+
+        fs::write(&path, "[theme]\nname = \"dracula\"\n").unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.message.contains("unknown theme"), "{}", err);
+    }
+}
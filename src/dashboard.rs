@@ -0,0 +1,425 @@
+use crate::config::Config;
+use crate::format::{self, format_state, format_token_count, format_usd, tool_label};
+use crate::icons::Icons;
+use crate::layout::{self, visible_width};
+use crate::pricing;
+use crate::session::{self, SessionState};
+use crate::theme::Theme;
+use crate::time::{calculate_elapsed, format_duration, parse_timestamp};
+use crate::transcript::{relative_path, AgentRun, DiffStats, Status, ToolCall, TranscriptState};
+use std::collections::BTreeSet;
+
+// ============================================================================
+// Dashboard (`claude-status dashboard`)
+// ============================================================================
+
+/// Below this many columns the panels are stacked instead of side by side.
+const MIN_SPLIT_WIDTH: usize = 80;
+
+/// Render the dashboard to fill a terminal of `(width, height)`: exactly
+/// `height` lines of at most `width` columns. `title` goes in the header,
+/// usually the transcript's file name.
+pub fn render_dashboard(
+    state: &TranscriptState,
+    config: &Config,
+    theme: &Theme,
+    icons: &Icons,
+    title: &str,
+    (width, height): (usize, usize),
+    now_ms: u64,
+) -> Vec<String> {
+    let mut lines = vec![layout::truncate_visible(&header(state, config, theme, icons, title, now_ms), width)];
+
+    // Todos and agents on top, the tool log in the middle, files and usage
+    // at the bottom. The log gets whatever the other rows leave.
+    let body = height.saturating_sub(1);
+    let split = width >= MIN_SPLIT_WIDTH;
+    let rows = if split { 1 } else { 2 };
+    let top = (body * 3 / 10).max(3 * rows).min(body);
+    let bottom = (body * 3 / 10).max(3 * rows).min(body - top);
+    let middle = body - top - bottom;
+
+    let todos = todo_lines(state, theme, icons);
+    let todo_title = format!("Todos {}/{}", state.todos.done, state.todos.total);
+    let left = width / 2;
+    let agent_width = if split { width - left } else { width };
+    // The newest agents that fit, so their timelines use the whole width
+    let agent_height = if split { top } else { top - top / 2 };
+    let runs = &state.agent_log[state.agent_log.len().saturating_sub(agent_height.saturating_sub(2))..];
+    let agents = agent_lines(runs, theme, icons, agent_width.saturating_sub(4), now_ms);
+    let agent_title = format!("Agents {}", state.agent_log.len());
+    lines.extend(row(theme, (&todo_title, todos), (&agent_title, agents), width, top, split));
+
+    let log = log_lines(state, config, theme, icons, now_ms);
+    let log_title = format!("Tools {} calls", state.tools.log.len());
+    lines.extend(panel(theme, &log_title, tail(log, middle), width, middle));
+
+    let (file_count, files) = file_lines(state, theme);
+    let file_title = format!("Files {}", file_count);
+    let usage = usage_lines(state, config, theme, icons, now_ms);
+    lines.extend(row(theme, (&file_title, files), ("Usage", usage), width, bottom, split));
+
+    lines.truncate(height);
+    lines.resize(height, String::new());
+    lines
+}
+
+/// `transcript.jsonl  ⟳ working  12m04s`
+fn header(state: &TranscriptState, config: &Config, theme: &Theme, icons: &Icons, title: &str, now_ms: u64) -> String {
+    let Theme { accent, separator, reset: nc, .. } = theme;
+
+    let mut parts = vec![format!("{accent}{title}{nc}")];
+    if state.session.last_activity.is_some() || state.session.state != SessionState::Idle {
+        let current = session::effective_state(state, &config.segments.state, now_ms);
+        parts.extend(format_state(current, state.session.error.as_deref(), theme, icons, &layout::FULL));
+    }
+    if state.session.started_at.is_some() {
+        // A finished session stops the clock at its last line
+        let end = match state.session.state {
+            SessionState::Working | SessionState::AwaitingPermission => None,
+            _ => state.session.last_activity.clone(),
+        };
+        parts.push(format_duration(calculate_elapsed(&state.session.started_at, &end, now_ms)));
+    }
+    parts.push(format!("{separator}Ctrl-C to quit{nc}"));
+
+    parts.join(&format!(" {separator}|{nc} "))
+}
+
+/// Two panels side by side, or stacked below [`MIN_SPLIT_WIDTH`].
+fn row(
+    theme: &Theme,
+    (left_title, left_lines): (&str, Vec<String>),
+    (right_title, right_lines): (&str, Vec<String>),
+    width: usize,
+    height: usize,
+    split: bool,
+) -> Vec<String> {
+    if !split {
+        let upper = height / 2;
+        let mut lines = panel(theme, left_title, left_lines, width, upper);
+        lines.extend(panel(theme, right_title, right_lines, width, height - upper));
+        return lines;
+    }
+
+    let left_width = width / 2;
+    let left = panel(theme, left_title, left_lines, left_width, height);
+    let right = panel(theme, right_title, right_lines, width - left_width, height);
+    left.into_iter().zip(right).map(|(l, r)| l + &r).collect()
+}
+
+/// A box of exactly `width` × `height` around `lines`. Lines that don't
+/// fit are cut off with a `… N more` marker.
+fn panel(theme: &Theme, title: &str, mut lines: Vec<String>, width: usize, height: usize) -> Vec<String> {
+    let Theme { accent, separator: border, reset: nc, .. } = theme;
+
+    // Room for the corners and one column of title at least
+    if height == 0 || width < 5 {
+        return vec![" ".repeat(width); height];
+    }
+
+    let inner = width - 4;
+    let title = layout::truncate_width(title, inner.saturating_sub(1));
+    let rule = "─".repeat(width - 5 - visible_width(&title));
+    let mut out = vec![format!("{border}┌─{nc} {accent}{title}{nc} {border}{rule}┐{nc}")];
+    if height == 1 {
+        return out;
+    }
+
+    let rows = height - 2;
+    if lines.len() > rows && rows > 0 {
+        let hidden = lines.len() - rows + 1;
+        lines.truncate(rows - 1);
+        lines.push(format!("{border}… {hidden} more{nc}"));
+    }
+    lines.resize(rows, String::new());
+
+    for line in lines {
+        let line = layout::truncate_visible(&line, inner);
+        let pad = " ".repeat(inner - visible_width(&line));
+        out.push(format!("{border}│{nc} {line}{pad} {border}│{nc}"));
+    }
+    out.push(format!("{border}└{}┘{nc}", "─".repeat(width - 2)));
+    out
+}
+
+/// The last lines that fit in a panel of `height`, so the log scrolls.
+fn tail(mut lines: Vec<String>, height: usize) -> Vec<String> {
+    let rows = height.saturating_sub(2);
+    if lines.len() > rows {
+        lines.drain(..lines.len() - rows);
+    }
+    lines
+}
+
+/// The whole todo list with a status icon per item.
+fn todo_lines(state: &TranscriptState, theme: &Theme, icons: &Icons) -> Vec<String> {
+    let Theme { complete, running, separator, reset: nc, .. } = theme;
+
+    if state.todos.items.is_empty() {
+        return vec![format!("{separator}no todo list{nc}")];
+    }
+    state
+        .todos
+        .items
+        .iter()
+        .map(|todo| match todo.status.as_str() {
+            "completed" => format!("{complete}{}{nc} {separator}{}{nc}", icons.check, todo.content),
+            "in_progress" => {
+                let text = todo.active_form.as_deref().unwrap_or(&todo.content);
+                format!("{running}{} {}{nc}", icons.spinner, text)
+            }
+            _ => format!("{separator}{}{nc} {}", icons.pending, todo.content),
+        })
+        .collect()
+}
+
+/// One line per agent: status, type, a timeline bar spanning the first
+/// agent's start to the last one's end (or now), and the elapsed time.
+fn agent_lines(agents: &[AgentRun], theme: &Theme, icons: &Icons, width: usize, now_ms: u64) -> Vec<String> {
+    let Theme { complete, running, error, separator, reset: nc, .. } = theme;
+
+    if agents.is_empty() {
+        return vec![format!("{separator}no agents yet{nc}")];
+    }
+
+    let span = |a: &AgentRun| {
+        let start = a.start_time.as_deref().and_then(parse_timestamp).unwrap_or(now_ms);
+        let end = a.end_time.as_deref().and_then(parse_timestamp).unwrap_or(now_ms);
+        (start, end.max(start))
+    };
+    let first = agents.iter().map(|a| span(a).0).min().unwrap_or(now_ms);
+    let last = agents.iter().map(|a| span(a).1).max().unwrap_or(now_ms).max(first + 1);
+
+    let name_width = agents.iter().map(|a| visible_width(&a.agent_type)).max().unwrap_or(0).min(16);
+    // Icon, name, elapsed (`12m04s`) and the spaces between them
+    let bar_width = width.saturating_sub(name_width + 12).max(1);
+    let column = |ms: u64| ((ms - first) as u128 * bar_width as u128 / (last - first) as u128) as usize;
+
+    agents
+        .iter()
+        .map(|a| {
+            let (color, icon) = match a.status {
+                Status::Running => (running, icons.spinner),
+                Status::Completed => (complete, icons.check),
+                Status::Error => (error, icons.error),
+            };
+            let (start, end) = span(a);
+            let from = column(start).min(bar_width - 1);
+            let to = column(end).clamp(from + 1, bar_width);
+            let bar = format!(
+                "{separator}{}{nc}{color}{}{nc}{separator}{}{nc}",
+                "·".repeat(from),
+                "█".repeat(to - from),
+                "·".repeat(bar_width - to),
+            );
+            let name = layout::truncate_width(&a.agent_type, name_width);
+            let pad = " ".repeat(name_width - visible_width(&name));
+            let elapsed = format_duration(end - start);
+            format!("{color}{icon}{nc} {name}{pad} {bar} {elapsed}")
+        })
+        .collect()
+}
+
+/// Finished calls, oldest first, then the ones still running.
+fn log_lines(state: &TranscriptState, config: &Config, theme: &Theme, icons: &Icons, now_ms: u64) -> Vec<String> {
+    let Theme { complete, running, error, separator, reset: nc, .. } = theme;
+
+    let running_calls = state.tools.running.iter().map(|t| ToolCall {
+        name: t.name.clone(),
+        target: t.target.clone(),
+        start_time: t.start_time.clone(),
+        end_time: None,
+        is_error: false,
+    });
+    let running_count = state.tools.running.len();
+    let calls: Vec<ToolCall> = state.tools.log.iter().cloned().chain(running_calls).collect();
+    if calls.is_empty() {
+        return vec![format!("{separator}no tool calls yet{nc}")];
+    }
+
+    calls
+        .iter()
+        .enumerate()
+        .map(|(i, call)| {
+            let is_running = i >= calls.len() - running_count;
+            let (color, icon) = if is_running {
+                (running, icons.spinner)
+            } else if call.is_error {
+                (error, icons.error)
+            } else {
+                (complete, icons.check)
+            };
+            let time = call.start_time.as_deref().and_then(|t| t.get(11..19)).unwrap_or("--:--:--");
+            let target = match call.target.as_deref() {
+                Some(target) => format!(" {}", relative_path(target, state.files.workspace.as_deref())),
+                None => String::new(),
+            };
+            let elapsed = match call.start_time {
+                Some(_) => {
+                    let ms = calculate_elapsed(&call.start_time, &call.end_time, now_ms);
+                    let duration_color = format::duration_color(ms, &config.segments.tools, theme, separator);
+                    format!(" {duration_color}{}{nc}", format_duration(ms))
+                }
+                None => String::new(),
+            };
+            let label = tool_label(&call.name, &config.mcp, theme);
+            format!("{separator}{time}{nc} {color}{icon}{nc} {label}{target}{elapsed}")
+        })
+        .collect()
+}
+
+/// Files touched this session: `A` created, `M` modified, `R` only read,
+/// with lines changed per file. Returns the file count as well.
+fn file_lines(state: &TranscriptState, theme: &Theme) -> (usize, Vec<String>) {
+    let Theme { complete, slow, error, separator, reset: nc, .. } = theme;
+
+    let files = &state.files.session;
+    let workspace = state.files.workspace.as_deref();
+    let paths: BTreeSet<&String> = files.read.iter().chain(&files.created).chain(&files.modified).collect();
+    if paths.is_empty() {
+        return (0, vec![format!("{separator}no files touched{nc}")]);
+    }
+
+    let lines = paths
+        .iter()
+        .map(|path| {
+            let mark = if files.created.contains(*path) {
+                format!("{complete}A{nc}")
+            } else if files.modified.contains(*path) {
+                format!("{slow}M{nc}")
+            } else {
+                format!("{separator}R{nc}")
+            };
+            let diff = match state.diff.by_file.get(*path) {
+                Some(DiffStats { added, removed }) => format!(" {complete}+{added}{nc} {error}−{removed}{nc}"),
+                None => String::new(),
+            };
+            format!("{mark} {}{diff}", relative_path(path, workspace))
+        })
+        .collect();
+    (paths.len(), lines)
+}
+
+/// Token, cost, line, and compaction counters for the turn and session.
+fn usage_lines(state: &TranscriptState, config: &Config, theme: &Theme, icons: &Icons, now_ms: u64) -> Vec<String> {
+    let Theme { complete, error, separator, reset: nc, .. } = theme;
+
+    let usage = &state.usage;
+    let estimate = pricing::estimate(usage, config);
+    let turn_session = |turn: String, session: String| format!("{turn} {separator}turn /{nc} {session} {separator}session{nc}");
+
+    let mut lines = vec![format!(
+        "{} Tokens  {}",
+        icons.tokens,
+        turn_session(format_token_count(usage.turn.total()), format_token_count(usage.session.total()))
+    )];
+    if let Some(ratio) = usage.session.cache_hit_ratio() {
+        lines.push(format!("  Cache   {:.0}% read from cache", ratio * 100.0));
+    }
+
+    let unpriced = if estimate.unpriced_models.is_empty() { "" } else { "+" };
+    lines.push(format!(
+        "{} Cost    {}",
+        icons.cost,
+        turn_session(
+            format!("{}{unpriced}", format_usd(estimate.turn_usd)),
+            format!("{}{unpriced}", format_usd(estimate.session_usd))
+        )
+    ));
+    for (model, model_usage) in &usage.by_model {
+        let cost = match estimate.by_model.get(model) {
+            Some((_, session)) => format_usd(*session),
+            None => "unpriced".to_string(),
+        };
+        lines.push(format!(
+            "  {separator}{model}{nc} {} {cost}",
+            format_token_count(model_usage.session.total())
+        ));
+    }
+
+    let stats = |d: &DiffStats| format!("{complete}+{}{nc} {error}−{}{nc}", d.added, d.removed);
+    lines.push(format!(
+        "{} Lines   {}",
+        icons.files,
+        turn_session(stats(&state.diff.turn), stats(&state.diff.session))
+    ));
+
+    let compaction = &state.compaction;
+    if compaction.count > 0 {
+        let since = calculate_elapsed(&compaction.last_time, &None, now_ms);
+        lines.push(format!(
+            "{} Compacted ×{} {separator}last {} ago{nc}",
+            icons.compaction,
+            compaction.count,
+            format_duration(since)
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icons::IconSet;
+    use crate::theme::{builtin, ColorMode};
+    use crate::transcript::TranscriptParser;
+
+    #[test]
+    fn test_dashboard_panels() {
+        let config = Config::default();
+        let mut parser = TranscriptParser::default();
+        for line in [
+            r#"{"type":"user","timestamp":"2025-01-15T10:29:00Z","message":{"content":"plan it"}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:29:01Z","message":{"content":[{"type":"tool_use","id":"a0","name":"Task","input":{"subagent_type":"Plan","prompt":"plan"}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-15T10:29:30Z","message":{"content":[{"type":"tool_result","tool_use_id":"a0","content":"done"}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:29:31Z","message":{"stop_reason":"end_turn","content":[{"type":"text","text":"Planned."}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-15T10:30:00Z","cwd":"/repo","message":{"content":"fix auth"}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:01Z","message":{"content":[{"type":"tool_use","id":"t0","name":"TodoWrite","input":{"todos":[{"content":"Read auth","activeForm":"Reading auth","status":"completed"},{"content":"Fix login","activeForm":"Fixing login","status":"in_progress"},{"content":"Run tests","activeForm":"Running tests","status":"pending"}]}}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:02Z","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/repo/src/auth.rs"}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-15T10:30:04Z","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:05Z","message":{"content":[{"type":"tool_use","id":"t2","name":"Task","input":{"subagent_type":"Explore","prompt":"find callers"}}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:06Z","message":{"content":[{"type":"tool_use","id":"t3","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+        ] {
+            parser.process_line(line);
+        }
+        let state = parser.snapshot(&config);
+        assert_eq!(state.todos.items.len(), 3);
+        assert_eq!(state.agents.len(), 1);
+        assert_eq!(state.agent_log.len(), 2);
+        assert_eq!(state.tools.log.len(), 1);
+        assert_eq!(state.tools.log[0].target.as_deref(), Some("/repo/src/auth.rs"));
+
+        let theme = Theme::new(&builtin("catppuccin-mocha").unwrap(), ColorMode::None);
+        let icons = IconSet::Ascii.icons();
+        let now = parse_timestamp("2025-01-15T10:30:10Z").unwrap();
+        let lines = render_dashboard(&state, &config, &theme, &icons, "t.jsonl", (100, 30), now);
+
+        assert_eq!(lines.len(), 30);
+        assert!(lines.iter().all(|l| visible_width(l) <= 100));
+        assert!(lines[0].starts_with("t.jsonl | ... working | 1m10s"));
+        let text = lines.join("\n");
+        for expected in [
+            "Todos 1/3",
+            "[ok] Read auth",
+            "... Fixing login",
+            "[ ] Run tests",
+            "Agents 2",
+            "[ok] Plan    ",
+            "... Explore ",
+            "10:30:02 [ok] Read src/auth.rs 2s",
+            "10:30:06 ... Bash cargo test 4s",
+            "R src/auth.rs",
+        ] {
+            assert!(text.contains(expected), "missing {expected:?} in\n{text}");
+        }
+
+        // Tiny panes, where stacked panels get only a few columns
+        for size in [(9, 20), (8, 12), (4, 6), (1, 3), (0, 0)] {
+            let lines = render_dashboard(&state, &config, &theme, &icons, "t.jsonl", size, now);
+            assert_eq!(lines.len(), size.1);
+            assert!(lines.iter().all(|l| visible_width(l) <= size.0), "{size:?}");
+        }
+    }
+}
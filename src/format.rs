@@ -0,0 +1,897 @@
+use crate::config::{self, Config, Segment};
+use crate::icons::Icons;
+use crate::layout::{self, Detail};
+use crate::pricing::{self, CostEstimate};
+use crate::session::{self, SessionState};
+use crate::theme::Theme;
+use crate::time::{calculate_elapsed, format_duration, now_millis};
+use crate::transcript::{
+    parse_mcp_name, AgentEntry, BackgroundJob, CompactionState, DiffState, DiffStats, FileState, JobStatus, RunningTool, SkillEntry, Status, TodoState, ToolDuration,
+    ToolState, TranscriptState, UsageState,
+};
+use std::collections::HashMap;
+
+// ============================================================================
+// Output Formatting
+// ============================================================================
+
+/// Render line 2. With a `width`, segments are shrunk level by level until
+/// the line fits, and hard-truncated as a last resort.
+pub fn format_output(
+    state: &TranscriptState,
+    config: &Config,
+    theme: &Theme,
+    icons: &Icons,
+    width: Option<usize>,
+) -> String {
+    let Some(width) = width else {
+        return format_segments(state, config, theme, icons, &layout::FULL);
+    };
+
+    let mut output = String::new();
+    for detail in layout::LEVELS {
+        output = format_segments(state, config, theme, icons, detail);
+        if layout::visible_width(&output) <= width {
+            return output;
+        }
+    }
+    layout::truncate_visible(&output, width)
+}
+
+/// Render line 2 at a fixed level of detail.
+pub fn format_segments(
+    state: &TranscriptState,
+    config: &Config,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> String {
+    let parts: Vec<String> = config
+        .segments
+        .visible()
+        .filter_map(|segment| match segment {
+            // Nothing to classify before the first message
+            Segment::State if state.session.last_activity.is_none() && state.session.state == SessionState::Idle => None,
+            Segment::State => {
+                let current = session::effective_state(state, &config.segments.state, now_millis());
+                format_state(current, state.session.error.as_deref(), theme, icons, detail)
+            }
+            Segment::Todos => format_todos(&state.todos, theme, icons, detail),
+            Segment::Skills => format_skills(&state.skills, theme, icons, detail),
+            Segment::Agents => format_agents(&state.agents, &config.segments.agents, theme, icons, detail),
+            Segment::Jobs => format_jobs(&state.jobs, theme, icons, detail),
+            Segment::Tools => {
+                format_tools(&state.tools, &config.segments.tools, &config.mcp, theme, icons, detail)
+            }
+            Segment::Files => format_files(&state.files, &config.segments.files, theme, icons, detail),
+            Segment::Diff => format_diff(&state.diff, &config.segments.diff, theme, detail),
+            Segment::Tokens => format_tokens(&state.usage, &config.segments.tokens, theme, icons, detail),
+            Segment::Cost => {
+                let estimate = pricing::estimate(&state.usage, config);
+                format_cost(&estimate, &config.segments.cost, theme, icons, detail)
+            }
+            Segment::Compaction => {
+                format_compaction(&state.compaction, &config.segments.compaction, theme, icons, detail, now_millis())
+            }
+        })
+        .collect();
+
+    if parts.is_empty() {
+        return String::new();
+    }
+
+    parts.join(&format!(" {}|{} ", theme.separator, theme.reset))
+}
+
+/// State indicator, e.g. `⏸ waiting for approval`. The API error message
+/// follows its label at full width.
+pub fn format_state(
+    state: SessionState,
+    error: Option<&str>,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> Option<String> {
+    let nc = &theme.reset;
+    let (color, icon, label) = match state {
+        SessionState::Working => (&theme.running, icons.spinner, "working"),
+        SessionState::AwaitingPermission => (&theme.slow, icons.waiting, "waiting for approval"),
+        SessionState::Interrupted => (&theme.error, icons.interrupted, "interrupted"),
+        SessionState::Idle => (&theme.complete, icons.idle, "idle"),
+        SessionState::ApiError => (&theme.error, icons.error, "API error"),
+    };
+
+    if !detail.state_label {
+        return Some(format!("{color}{icon}{nc}"));
+    }
+    let message = match (error, detail.target_width) {
+        (Some(message), width) if state == SessionState::ApiError && width != Some(0) => {
+            let width = width.unwrap_or(ERROR_MESSAGE_WIDTH);
+            format!(": {}", layout::truncate_width(message, width))
+        }
+        _ => String::new(),
+    };
+    Some(format!("{color}{icon} {label}{message}{nc}"))
+}
+
+/// Compact `⟳2 ✓3 ✗1` summary used when a segment is collapsed.
+fn format_status_counts<'a>(statuses: impl Iterator<Item = &'a Status>, theme: &Theme, icons: &Icons) -> String {
+    let (mut running, mut completed, mut errors) = (0, 0, 0);
+    for status in statuses {
+        match status {
+            Status::Running => running += 1,
+            Status::Completed => completed += 1,
+            Status::Error => errors += 1,
+        }
+    }
+
+    [
+        (running, &theme.running, icons.spinner),
+        (completed, &theme.complete, icons.check),
+        (errors, &theme.error, icons.error),
+    ]
+    .iter()
+    .filter(|(count, _, _)| *count > 0)
+    .map(|(count, color, icon)| format!("{color}{icon}{}{count}", theme.reset))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Skills segment, or `None` when no skill ran this turn.
+pub fn format_skills(skills: &[SkillEntry], theme: &Theme, icons: &Icons, detail: &Detail) -> Option<String> {
+    let Theme { complete, running, error, accent, reset: nc, .. } = theme;
+
+    if skills.is_empty() {
+        return None;
+    }
+
+    if detail.collapse_skills {
+        let counts = format_status_counts(skills.iter().map(|s| &s.status), theme, icons);
+        return Some(format!("{accent}{}{nc} {}", icons.skills, counts));
+    }
+
+    let parts: Vec<String> = skills
+        .iter()
+        .map(|s| {
+            let (color, icon) = match s.status {
+                Status::Running => (running, icons.spinner),
+                Status::Completed => (complete, icons.check),
+                Status::Error => (error, icons.error),
+            };
+
+            format!("{color}{icon}{nc} {}", s.name)
+        })
+        .collect();
+
+    Some(format!("{accent}{}{nc} {}", icons.skills, parts.join(" ")))
+}
+
+/// Todos segment, or `None` without a todo list.
+pub fn format_todos(todos: &TodoState, theme: &Theme, icons: &Icons, detail: &Detail) -> Option<String> {
+    let Theme { complete, running, accent, reset: nc, .. } = theme;
+
+    if todos.total == 0 {
+        return None;
+    }
+
+    let (color, icon) = if todos.done == todos.total {
+        (complete, icons.check)
+    } else {
+        (running, icons.spinner)
+    };
+
+    let section = icons.todos;
+    let current = match (todos.current.as_deref(), detail.todo_width) {
+        (_, Some(0)) | (None, _) => None,
+        (Some(current), Some(width)) => Some(layout::truncate_width(current, width)),
+        (Some(current), None) => Some(current.to_string()),
+    };
+
+    let text = if let Some(ref current) = current {
+        if todos.done < todos.total {
+            format!(
+                "{accent}{section}{nc} {color}{icon}{nc} {current} ({}/{})",
+                todos.done, todos.total
+            )
+        } else {
+            format!(
+                "{accent}{section}{nc} {color}{icon}{nc} All done ({}/{})",
+                todos.done, todos.total
+            )
+        }
+    } else {
+        format!(
+            "{accent}{section}{nc} {color}{icon}{nc} {}/{}",
+            todos.done, todos.total
+        )
+    };
+
+    Some(text)
+}
+
+/// Agents segment with elapsed times and, for running agents, their
+/// current tool. `None` without agents.
+pub fn format_agents(
+    agents: &[AgentEntry],
+    settings: &config::AgentsSegment,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> Option<String> {
+    let Theme { complete, running, error, accent, reset: nc, .. } = theme;
+
+    if agents.is_empty() {
+        return None;
+    }
+
+    if detail.collapse_agents {
+        let counts = format_status_counts(agents.iter().map(|a| &a.status), theme, icons);
+        return Some(format!("{accent}{}{nc} {}", icons.agents, counts));
+    }
+
+    let now = now_millis();
+
+    let parts: Vec<String> = agents
+        .iter()
+        .map(|a| {
+            let (color, icon) = match a.status {
+                Status::Running => (running, icons.spinner),
+                Status::Completed => (complete, icons.check),
+                Status::Error => (error, icons.error),
+            };
+
+            let elapsed = calculate_elapsed(&a.start_time, &a.end_time, now);
+            let elapsed_str = if elapsed > 0 {
+                format!(" ({})", format_duration(elapsed))
+            } else {
+                String::new()
+            };
+
+            let activity = match a.activity.current() {
+                Some(tool) if a.status == Status::Running && settings.show_activity && detail.agent_activity => {
+                    format_agent_action(tool, theme, icons, detail)
+                }
+                _ => String::new(),
+            };
+
+            format!("{color}{icon}{nc} {}{}{}", a.agent_type, elapsed_str, activity)
+        })
+        .collect();
+
+    Some(format!("{accent}{}{nc} {}", icons.agents, parts.join(" ")))
+}
+
+/// ` ⟳ Grep "auth"` suffix for an agent's current tool. Patterns are quoted
+/// so they read as search terms rather than paths.
+fn format_agent_action(tool: &RunningTool, theme: &Theme, icons: &Icons, detail: &Detail) -> String {
+    let Theme { running, reset: nc, .. } = theme;
+
+    let target = match (tool.target.as_deref(), detail.target_width) {
+        (Some(_), Some(0)) | (None, _) => String::new(),
+        (Some(t), width) => {
+            let t = match width {
+                Some(width) => layout::truncate_width(t, width),
+                None => t.to_string(),
+            };
+            if matches!(tool.name.as_str(), "Grep" | "Glob") {
+                format!(" \"{}\"", t)
+            } else {
+                format!(" {}", t)
+            }
+        }
+    };
+
+    let name = match parse_mcp_name(&tool.name) {
+        Some((server, tool)) => format!("{}:{}", server, tool),
+        None => tool.name.clone(),
+    };
+    format!(" {running}{}{nc} {}{}", icons.spinner, name, target)
+}
+
+/// Background jobs segment: running shells with their command and age,
+/// then ones that finished this turn. `None` without jobs.
+pub fn format_jobs(jobs: &[BackgroundJob], theme: &Theme, icons: &Icons, detail: &Detail) -> Option<String> {
+    let Theme { complete, running, error, accent, reset: nc, .. } = theme;
+
+    if jobs.is_empty() {
+        return None;
+    }
+
+    let status_of = |job: &BackgroundJob| match job.status {
+        JobStatus::Running => Status::Running,
+        JobStatus::Completed => Status::Completed,
+        JobStatus::Failed | JobStatus::Killed => Status::Error,
+    };
+
+    if detail.collapse_jobs {
+        let statuses: Vec<Status> = jobs.iter().map(status_of).collect();
+        let counts = format_status_counts(statuses.iter(), theme, icons);
+        return Some(format!("{accent}{}{nc} {}", icons.jobs, counts));
+    }
+
+    let now = now_millis();
+
+    let parts: Vec<String> = jobs
+        .iter()
+        .map(|job| {
+            let (color, icon) = match status_of(job) {
+                Status::Running => (running, icons.spinner),
+                Status::Completed => (complete, icons.check),
+                Status::Error => (error, icons.error),
+            };
+
+            let command = match detail.target_width {
+                Some(0) => String::new(),
+                Some(width) => format!(" {}", layout::truncate_width(&job.command, width)),
+                None => format!(" {}", layout::truncate_width(&job.command, JOB_COMMAND_WIDTH)),
+            };
+            let elapsed = calculate_elapsed(&job.start_time, &None, now);
+            let suffix = match (job.status, job.exit_code) {
+                (JobStatus::Running, _) if elapsed > 0 => format!(" ({})", format_duration(elapsed)),
+                (JobStatus::Killed, _) => " (killed)".to_string(),
+                (JobStatus::Failed, Some(code)) => format!(" (exit {})", code),
+                _ => String::new(),
+            };
+
+            format!("{color}{icon}{nc}{command}{suffix}")
+        })
+        .collect();
+
+    Some(format!("{accent}{}{nc} {}", icons.jobs, parts.join(" ")))
+}
+
+/// Background commands tend to be long (`npm run dev -- --port 3000`), so
+/// they're cut even at full detail.
+const JOB_COMMAND_WIDTH: usize = 25;
+
+/// Tool name for display: MCP tools become `server:tool`, or `<icon> tool`
+/// with an icon configured for the server, in the server's color if set.
+pub fn tool_label(name: &str, mcp: &config::McpConfig, theme: &Theme) -> String {
+    let Some((server, tool)) = parse_mcp_name(name) else {
+        return name.to_string();
+    };
+    let text = match mcp.servers.get(server).and_then(|s| s.icon.as_deref()) {
+        Some(icon) => format!("{} {}", icon, tool),
+        None => format!("{}:{}", server, tool),
+    };
+    colorize_server(server, text, theme)
+}
+
+/// MCP server name (or its icon) for grouped counts.
+fn server_label(server: &str, mcp: &config::McpConfig, theme: &Theme) -> String {
+    let text = mcp
+        .servers
+        .get(server)
+        .and_then(|s| s.icon.clone())
+        .unwrap_or_else(|| server.to_string());
+    colorize_server(server, text, theme)
+}
+
+fn colorize_server(server: &str, text: String, theme: &Theme) -> String {
+    match theme.mcp_servers.get(server) {
+        Some(color) => format!("{color}{text}{}", theme.reset),
+        None => text,
+    }
+}
+
+/// Columns for the last error message at full detail.
+const ERROR_MESSAGE_WIDTH: usize = 30;
+
+/// Tool calls shorter than this don't get a duration, so fast reads and
+/// greps don't clutter the line.
+const MIN_TOOL_DURATION_MS: u64 = 1000;
+
+/// Color for a tool call that has taken `ms`, escalating past the configured
+/// thresholds.
+pub(crate) fn duration_color<'a>(ms: u64, limits: &config::ToolsSegment, theme: &'a Theme, normal: &'a str) -> &'a str {
+    let exceeds = |secs: u64| secs > 0 && ms >= secs * 1000;
+    if exceeds(limits.stuck_after_secs) {
+        &theme.error
+    } else if exceeds(limits.slow_after_secs) {
+        &theme.slow
+    } else {
+        normal
+    }
+}
+
+/// ` (12s)` suffix for a tool, or empty when too short or hidden by `detail`.
+fn format_tool_elapsed(ms: u64, color: &str, nc: &str, detail: &Detail) -> String {
+    if !detail.durations || ms < MIN_TOOL_DURATION_MS {
+        return String::new();
+    }
+    format!(" {color}({}){nc}", format_duration(ms))
+}
+
+/// Tools segment: most-used completed tools, then running ones with
+/// targets. `None` when there's no tool activity.
+pub fn format_tools(
+    tools: &ToolState,
+    limits: &config::ToolsSegment,
+    mcp: &config::McpConfig,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> Option<String> {
+    let Theme { complete, running: running_color, error, accent, reset: nc, .. } = theme;
+
+    let now = now_millis();
+    let mut parts: Vec<String> = vec![];
+
+    // Check if we have running file operations (these need more space for paths)
+    let has_file_ops = tools.running.iter().any(|t| {
+        matches!(t.name.as_str(), "Read" | "Write" | "Edit" | "MultiEdit" | "NotebookEdit")
+    });
+
+    // Show fewer completed tools if we have file operations running
+    let max_completed = if !detail.completed_tools {
+        0
+    } else if has_file_ops {
+        limits.show_completed_with_file_ops
+    } else {
+        limits.show_completed
+    };
+
+    // (label, count, duration), merging MCP tools per server when grouped
+    let mut completed: Vec<(String, u32, ToolDuration)> = vec![];
+    let mut group_index: HashMap<&str, usize> = HashMap::new();
+    for (name, count) in &tools.completed {
+        let duration = tools.durations.get(name).copied().unwrap_or_default();
+        let server = parse_mcp_name(name).map(|(server, _)| server).filter(|_| limits.group_mcp);
+
+        let Some(server) = server else {
+            completed.push((tool_label(name, mcp, theme), *count, duration));
+            continue;
+        };
+        match group_index.get(server) {
+            Some(&i) => {
+                let entry = &mut completed[i];
+                entry.1 += count;
+                entry.2.total_ms += duration.total_ms;
+                entry.2.max_ms = entry.2.max_ms.max(duration.max_ms);
+            }
+            None => {
+                group_index.insert(server, completed.len());
+                completed.push((server_label(server, mcp, theme), *count, duration));
+            }
+        }
+    }
+    completed.sort_by_key(|(_, count, _)| std::cmp::Reverse(*count));
+
+    for (label, count, duration) in completed.iter().take(max_completed) {
+        let suffix = if *count > 1 {
+            format!(" ×{}", count)
+        } else {
+            String::new()
+        };
+        let color = duration_color(duration.max_ms, limits, theme, complete);
+        let elapsed = format_tool_elapsed(duration.total_ms, color, nc, detail);
+        parts.push(format!("{color}{}{nc} {}{}{}", icons.check, label, suffix, elapsed));
+    }
+
+    // Failed tools are always shown, with the latest message after its tool
+    let mut errors: Vec<_> = tools.errors.iter().collect();
+    errors.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    for (name, count) in errors {
+        let suffix = if *count > 1 {
+            format!(" ×{}", count)
+        } else {
+            String::new()
+        };
+        let message = match (&tools.last_error, detail.target_width) {
+            (Some(last), width) if &last.tool == name && limits.show_error_message && width != Some(0) => {
+                let width = width.unwrap_or(ERROR_MESSAGE_WIDTH);
+                format!(" {error}{}{nc}", layout::truncate_width(&last.message, width))
+            }
+            _ => String::new(),
+        };
+        let label = tool_label(name, mcp, theme);
+        parts.push(format!("{error}{}{nc} {}{}{}", icons.error, label, suffix, message));
+    }
+
+    // Show running tools - file ops first (they have paths)
+    let mut running: Vec<_> = tools.running.iter().collect();
+    running.sort_by_key(|t| {
+        // File operations come first (lower sort key)
+        if matches!(t.name.as_str(), "Read" | "Write" | "Edit" | "MultiEdit" | "NotebookEdit") {
+            0
+        } else {
+            1
+        }
+    });
+
+    for tool in running.iter().take(limits.show_running) {
+        let target = match (tool.target.as_ref(), detail.target_width) {
+            (Some(_), Some(0)) | (None, _) => String::new(),
+            (Some(t), Some(width)) => format!(" {}", layout::truncate_width(t, width)),
+            (Some(t), None) => format!(" {}", t),
+        };
+        let elapsed_ms = calculate_elapsed(&tool.start_time, &None, now);
+        let color = duration_color(elapsed_ms, limits, theme, running_color);
+        let elapsed = format_tool_elapsed(elapsed_ms, color, nc, detail);
+        let label = tool_label(&tool.name, mcp, theme);
+        parts.push(format!("{color}{}{nc} {}{}{}", icons.spinner, label, target, elapsed));
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(format!("{accent}{}{nc} {}", icons.tools, parts.join(" ")))
+    }
+}
+
+/// Files segment, e.g. `✎ 3 files ▸ auth.rs`, or `None` when no file was
+/// touched this turn.
+pub fn format_files(
+    files: &FileState,
+    settings: &config::FilesSegment,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> Option<String> {
+    let Theme { accent, reset: nc, .. } = theme;
+
+    let count = files.turn.len();
+    if count == 0 {
+        return None;
+    }
+
+    let noun = if count == 1 { "file" } else { "files" };
+    let mut text = format!("{accent}{}{nc} {} {}", icons.files, count, noun);
+
+    let last = files.last.as_deref().filter(|_| settings.show_last && detail.target_width != Some(0));
+    if let Some(last) = last {
+        let name = last.rsplit('/').next().unwrap_or(last);
+        let name = match detail.target_width {
+            Some(width) => layout::truncate_width(name, width),
+            None => name.to_string(),
+        };
+        text.push_str(&format!(" ▸ {}", name));
+    }
+
+    Some(text)
+}
+
+/// Diff segment, e.g. `+45 −12 / +310 −87`, or `None` when nothing was
+/// edited this turn.
+pub fn format_diff(diff: &DiffState, settings: &config::DiffSegment, theme: &Theme, detail: &Detail) -> Option<String> {
+    let Theme { complete, error, separator, reset: nc, .. } = theme;
+
+    if diff.turn.is_empty() {
+        return None;
+    }
+
+    let stats = |d: &DiffStats| format!("{complete}+{}{nc} {error}−{}{nc}", d.added, d.removed);
+    let mut text = stats(&diff.turn);
+    if settings.show_session && detail.usage_totals {
+        text.push_str(&format!(" {separator}/{nc} {}", stats(&diff.session)));
+    }
+
+    Some(text)
+}
+
+/// Token count with a k/M suffix: `850`, `12.3k`, `456k`, `1.24M`.
+pub fn format_token_count(n: u64) -> String {
+    if n < 1000 {
+        n.to_string()
+    } else if n < 100_000 {
+        format!("{:.1}k", n as f64 / 1000.0)
+    } else if n < 1_000_000 {
+        format!("{}k", n / 1000)
+    } else {
+        format!("{:.2}M", n as f64 / 1_000_000.0)
+    }
+}
+
+/// Tokens segment: this turn's tokens, then session total and cache hit
+/// ratio. `None` before any usage was reported.
+pub fn format_tokens(
+    usage: &UsageState,
+    settings: &config::TokensSegment,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> Option<String> {
+    let Theme { accent, separator, reset: nc, .. } = theme;
+
+    if usage.session.total() == 0 {
+        return None;
+    }
+
+    let mut text = format!("{accent}{}{nc} {}", icons.tokens, format_token_count(usage.turn.total()));
+    if settings.show_session && detail.usage_totals {
+        text.push_str(&format!(" {separator}/{nc} {}", format_token_count(usage.session.total())));
+        if let Some(ratio) = usage.session.cache_hit_ratio() {
+            text.push_str(&format!(" ({:.0}% cached)", ratio * 100.0));
+        }
+    }
+
+    Some(text)
+}
+
+/// Compaction segment, e.g. `⇊ ×2 12m04s ago`, or `None` before the first one.
+pub fn format_compaction(
+    compaction: &CompactionState,
+    settings: &config::CompactionSegment,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+    now_ms: u64,
+) -> Option<String> {
+    let Theme { accent, reset: nc, .. } = theme;
+
+    if compaction.count == 0 {
+        return None;
+    }
+
+    let mut text = format!("{accent}{}{nc} ×{}", icons.compaction, compaction.count);
+    if settings.show_since && detail.durations && compaction.last_time.is_some() {
+        let since = calculate_elapsed(&compaction.last_time, &None, now_ms);
+        text.push_str(&format!(" {} ago", format_duration(since)));
+    }
+
+    Some(text)
+}
+
+/// Dollar amount with three decimals below $1, two above.
+pub fn format_usd(usd: f64) -> String {
+    if usd < 1.0 {
+        format!("${:.3}", usd)
+    } else {
+        format!("${:.2}", usd)
+    }
+}
+
+/// Cost segment: this turn's estimated spend, then the session's. A
+/// trailing `+` means some models had no price. `None` until something
+/// could be priced.
+pub fn format_cost(
+    estimate: &CostEstimate,
+    settings: &config::CostSegment,
+    theme: &Theme,
+    icons: &Icons,
+    detail: &Detail,
+) -> Option<String> {
+    let Theme { accent, separator, reset: nc, .. } = theme;
+
+    if estimate.session_usd == 0.0 {
+        return None;
+    }
+
+    let mut text = format!("{accent}{}{nc} {}", icons.cost, format_usd(estimate.turn_usd));
+    if settings.show_session && detail.usage_totals {
+        text.push_str(&format!(" {separator}/{nc} {}", format_usd(estimate.session_usd)));
+    }
+    if !estimate.unpriced_models.is_empty() {
+        text.push('+');
+    }
+
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icons::IconSet;
+    use crate::test_support::temp_dir;
+    use crate::theme::{builtin, ColorMode};
+    use crate::config::TokensSegment;
+    use crate::transcript::ToolError;
+    use std::fs;
+
+    #[test]
+    fn test_config_reorders_and_disables_segments() {
+        assert_eq!(
+            Config::default().segments.visible().collect::<Vec<_>>(),
+            vec![Segment::State, Segment::Todos, Segment::Skills, Segment::Agents, Segment::Tools]
+        );
+
+        let dir = temp_dir("config");
+        let path = dir.join("opt-in.toml");
+        fs::write(&path, "[segments.cost]\nenabled = true\n").unwrap();
+        let config = config::load_from(&path).unwrap();
+        assert!(config.segments.visible().any(|s| s == Segment::Cost));
+
+        let path = dir.join("statusline.toml");
+        fs::write(&path, "[segments]\norder = [\"tools\", \"todos\"]\n\n[segments.todos]\nenabled = false\n").unwrap();
+        let config = config::load_from(&path).unwrap();
+        assert_eq!(config.segments.visible().collect::<Vec<_>>(), vec![Segment::Tools]);
+
+        let mut state = TranscriptState::default();
+        state.todos.total = 2;
+        state.tools.completed.insert("Read".to_string(), 1);
+        let output = format_output(&state, &config, &Theme::default(), &Icons::default(), None);
+        assert!(output.contains("Read"));
+        assert!(!output.contains("0/2"));
+    }
+
+    #[test]
+    fn test_ascii_icon_set() {
+        let mut state = TranscriptState::default();
+        state.tools.completed.insert("Read".to_string(), 2);
+        state.tools.running.push(RunningTool {
+            name: "Bash".to_string(),
+            target: Some("ls".to_string()),
+            start_time: None,
+        });
+        let theme = Theme::new(&builtin("nord").unwrap(), ColorMode::None);
+        let icons = IconSet::parse("ascii").unwrap().icons();
+
+        let output = format_output(&state, &Config::default(), &theme, &icons, None);
+        assert_eq!(output, "tools: [ok] Read ×2 ... Bash ls");
+
+        state.tools.errors.insert("Edit".to_string(), 2);
+        state.tools.last_error = Some(ToolError {
+            tool: "Edit".to_string(),
+            message: "String to replace not found in file.".to_string(),
+            timestamp: None,
+        });
+        let output = format_output(&state, &Config::default(), &theme, &icons, None);
+        assert_eq!(output, "tools: [ok] Read ×2 [x] Edit ×2 String to replace not found i… ... Bash ls");
+    }
+
+    #[test]
+    fn test_output_shrinks_to_width() {
+        let mut state = TranscriptState {
+            todos: TodoState {
+                current: Some("Refactoring the authentication middleware".to_string()),
+                done: 1,
+                total: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        state.tools.completed.insert("Grep".to_string(), 3);
+        state.tools.running.push(RunningTool {
+            name: "Edit".to_string(),
+            target: Some(".../middleware.rs".to_string()),
+            start_time: None,
+        });
+        for agent_type in ["Explore", "code-reviewer"] {
+            state.agents.push(AgentEntry {
+                agent_type: agent_type.to_string(),
+                status: Status::Running,
+                start_time: None,
+                end_time: None,
+                start_turn: 0,
+                agent_id: None,
+                activity: Default::default(),
+            });
+        }
+        let theme = Theme::new(&builtin("nord").unwrap(), ColorMode::None);
+        let icons = IconSet::parse("unicode").unwrap().icons();
+        let config = Config::default();
+
+        let full = format_output(&state, &config, &theme, &icons, None);
+        assert!(full.contains("✓ Grep ×3"));
+
+        let narrow = format_output(&state, &config, &theme, &icons, Some(60));
+        assert!(layout::visible_width(&narrow) <= 60, "{}", narrow);
+        assert!(!narrow.contains("Grep"), "{}", narrow);
+        assert!(narrow.contains("⟳ Edit"), "{}", narrow);
+
+        let tiny = format_output(&state, &config, &theme, &icons, Some(20));
+        assert_eq!(layout::visible_width(&tiny), 20, "{}", tiny);
+    }
+
+    #[test]
+    fn test_slow_tools_are_recolored() {
+        let mut state = TranscriptState::default();
+        state.tools.completed.insert("Grep".to_string(), 2);
+        state.tools.durations.insert("Grep".to_string(), ToolDuration { total_ms: 45_000, max_ms: 40_000 });
+        state.tools.running.push(RunningTool {
+            name: "Bash".to_string(),
+            target: None,
+            start_time: Some("2020-01-01T00:00:00Z".to_string()),
+        });
+        let theme = Theme::default();
+        let config = Config::default();
+        let output = format_tools(&state.tools, &config.segments.tools, &config.mcp, &theme, &Icons::default(), &layout::FULL).unwrap();
+
+        let (slow, error, nc) = (&theme.slow, &theme.error, &theme.reset);
+        assert!(output.contains(&format!("{slow}{}{nc} Grep ×2 {slow}(45s){nc}", Icons::default().check)), "{:?}", output);
+        assert!(output.contains(&format!("{error}{}{nc} Bash {error}(", Icons::default().spinner)), "{:?}", output);
+    }
+
+    #[test]
+    fn test_tokens_segment() {
+        let mut usage = UsageState::default();
+        assert_eq!(format_tokens(&usage, &TokensSegment::default(), &Theme::default(), &Icons::default(), &layout::FULL), None);
+
+        usage.turn.output_tokens = 12_345;
+        usage.session.output_tokens = 12_345;
+        usage.session.input_tokens = 500_000;
+        usage.session.cache_read_input_tokens = 1_500_000;
+        let theme = Theme::new(&builtin("nord").unwrap(), ColorMode::None);
+        let icons = IconSet::Unicode.icons();
+        let settings = TokensSegment::default();
+
+        let full = format_tokens(&usage, &settings, &theme, &icons, &layout::FULL).unwrap();
+        assert_eq!(full, "Σ 12.3k / 2.01M (75% cached)");
+        let compact = format_tokens(&usage, &settings, &theme, &icons, layout::LEVELS.last().unwrap()).unwrap();
+        assert_eq!(compact, "Σ 12.3k");
+        assert_eq!(format_token_count(850), "850");
+        assert_eq!(format_token_count(456_789), "456k");
+    }
+
+    #[test]
+    fn test_files_and_diff_segments() {
+        let mut files = FileState::default();
+        files.turn.read.insert("/repo/src/main.rs".to_string());
+        files.turn.modified.insert("/repo/src/auth.rs".to_string());
+        files.turn.created.insert("/repo/src/auth.rs".to_string());
+        files.last = Some("/repo/src/auth.rs".to_string());
+        let theme = Theme::new(&builtin("nord").unwrap(), ColorMode::None);
+        let icons = IconSet::Unicode.icons();
+        let settings = config::FilesSegment::default();
+
+        let full = format_files(&files, &settings, &theme, &icons, &layout::FULL).unwrap();
+        assert_eq!(full, "✎ 2 files ▸ auth.rs");
+        let compact = format_files(&files, &settings, &theme, &icons, layout::LEVELS.last().unwrap()).unwrap();
+        assert_eq!(compact, "✎ 2 files");
+
+        let mut diff = DiffState::default();
+        assert_eq!(format_diff(&diff, &config::DiffSegment::default(), &theme, &layout::FULL), None);
+        diff.turn = DiffStats { added: 45, removed: 12 };
+        diff.session = DiffStats { added: 310, removed: 87 };
+        let full = format_diff(&diff, &config::DiffSegment::default(), &theme, &layout::FULL).unwrap();
+        assert_eq!(full, "+45 −12 / +310 −87");
+    }
+
+    #[test]
+    fn test_agent_shows_current_action() {
+        let mut agent = AgentEntry {
+            agent_type: "Explore".to_string(),
+            status: Status::Running,
+            start_time: None,
+            end_time: None,
+            start_turn: 0,
+            agent_id: Some("a1".to_string()),
+            activity: Default::default(),
+        };
+        agent.activity.running.push(RunningTool {
+            name: "Grep".to_string(),
+            target: Some("auth".to_string()),
+            start_time: None,
+        });
+        let theme = Theme::new(&builtin("nord").unwrap(), ColorMode::None);
+        let icons = IconSet::Unicode.icons();
+        let settings = config::AgentsSegment::default();
+
+        let output = format_agents(&[agent.clone()], &settings, &theme, &icons, &layout::FULL).unwrap();
+        assert_eq!(output, "◈ ⟳ Explore ⟳ Grep \"auth\"");
+
+        let hidden = config::AgentsSegment { show_activity: false, ..settings };
+        assert_eq!(format_agents(&[agent], &hidden, &theme, &icons, &layout::FULL).unwrap(), "◈ ⟳ Explore");
+    }
+
+    #[test]
+    fn test_mcp_tools_grouped_by_server() {
+        let dir = temp_dir("mcp");
+        let path = dir.join("statusline.toml");
+        fs::write(
+            &path,
+            "[theme]\ncolors = \"truecolor\"\n\n[segments.tools]\ngroup_mcp = true\n\n[mcp.servers.linear]\nicon = \"L\"\ncolor = \"#5e6ad2\"\n",
+        )
+        .unwrap();
+        let config = config::load_from(&path).unwrap();
+        let theme = Theme::from_config(&config);
+
+        let mut state = TranscriptState::default();
+        state.tools.completed.insert("mcp__github__get_issue".to_string(), 2);
+        state.tools.completed.insert("mcp__github__create_issue".to_string(), 1);
+        state.tools.running.push(RunningTool {
+            name: "mcp__linear__list_issues".to_string(),
+            target: None,
+            start_time: None,
+        });
+        let tools = format_tools(&state.tools, &config.segments.tools, &config.mcp, &theme, &Icons::default(), &layout::FULL).unwrap();
+
+        assert!(tools.contains(" github ×3"), "{:?}", tools);
+        assert!(tools.contains("\x1b[38;2;94;106;210mL list_issues\x1b[0m"), "{:?}", tools);
+
+        let ungrouped = config::ToolsSegment::default();
+        let tools = format_tools(&state.tools, &ungrouped, &config.mcp, &theme, &Icons::default(), &layout::FULL).unwrap();
+        assert!(tools.contains(" github:get_issue ×2"), "{:?}", tools);
+    }
+}
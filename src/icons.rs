@@ -0,0 +1,145 @@
+use serde::Deserialize;
+
+// ============================================================================
+// Icon Sets
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconSet {
+    /// Requires a Nerd Font
+    #[default]
+    NerdFont,
+    /// Symbols available in most monospace fonts
+    Unicode,
+    Emoji,
+    /// Pure ASCII for CI logs and web terminals
+    Ascii,
+}
+
+impl IconSet {
+    pub const NAMES: &'static [&'static str] = &["nerd-font", "unicode", "emoji", "ascii"];
+
+    pub fn parse(name: &str) -> Option<IconSet> {
+        match name {
+            "nerd-font" => Some(IconSet::NerdFont),
+            "unicode" => Some(IconSet::Unicode),
+            "emoji" => Some(IconSet::Emoji),
+            "ascii" => Some(IconSet::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn icons(self) -> Icons {
+        match self {
+            IconSet::NerdFont => NERD_FONT,
+            IconSet::Unicode => UNICODE,
+            IconSet::Emoji => EMOJI,
+            IconSet::Ascii => ASCII,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Icons {
+    // Status
+    pub spinner: &'static str,
+    pub check: &'static str,
+    pub error: &'static str,
+    pub pending: &'static str,
+    pub waiting: &'static str,
+    pub interrupted: &'static str,
+    pub idle: &'static str,
+    // Sections
+    pub todos: &'static str,
+    pub agents: &'static str,
+    pub jobs: &'static str,
+    pub tools: &'static str,
+    pub files: &'static str,
+    pub skills: &'static str,
+    pub tokens: &'static str,
+    pub cost: &'static str,
+    pub compaction: &'static str,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        NERD_FONT
+    }
+}
+
+const NERD_FONT: Icons = Icons {
+    spinner: "\u{f110}",
+    check: "\u{f00c}",
+    error: "\u{f00d}",
+    pending: "\u{f10c}",     // circle outline
+    waiting: "\u{f28b}",     // pause circle
+    interrupted: "\u{f04d}", // stop
+    idle: "\u{f0f4}",        // coffee
+    todos: "\u{f14a}",  // checkbox
+    agents: "\u{ee0d}", // robot
+    jobs: "\u{f120}",   // terminal
+    tools: "\u{f0ad}",  // wrench
+    files: "\u{f044}",  // pencil square
+    skills: "\u{f0e7}", // lightning bolt
+    tokens: "\u{f0e4}", // dashboard
+    cost: "\u{f1ec}",   // calculator
+    compaction: "\u{f066}", // compress
+};
+
+const UNICODE: Icons = Icons {
+    spinner: "⟳",
+    check: "✓",
+    error: "✗",
+    pending: "○",
+    waiting: "⏸",
+    interrupted: "■",
+    idle: "◌",
+    todos: "☑",
+    agents: "◈",
+    jobs: "⚙",
+    tools: "⚒",
+    files: "✎",
+    skills: "✦",
+    tokens: "Σ",
+    cost: "≈",
+    compaction: "⇊",
+};
+
+const EMOJI: Icons = Icons {
+    spinner: "⏳",
+    check: "✅",
+    error: "❌",
+    pending: "⬜",
+    waiting: "✋",
+    interrupted: "🛑",
+    idle: "💤",
+    todos: "📋",
+    agents: "🤖",
+    jobs: "🐚",
+    tools: "🔧",
+    files: "📝",
+    skills: "⚡",
+    tokens: "🪙",
+    cost: "💰",
+    compaction: "🗜",
+};
+
+const ASCII: Icons = Icons {
+    spinner: "...",
+    check: "[ok]",
+    error: "[x]",
+    pending: "[ ]",
+    waiting: "[wait]",
+    interrupted: "[int]",
+    idle: "[idle]",
+    todos: "todo:",
+    agents: "agents:",
+    jobs: "jobs:",
+    tools: "tools:",
+    files: "files:",
+    skills: "skills:",
+    tokens: "tokens:",
+    cost: "cost:",
+    compaction: "compact:",
+};
@@ -0,0 +1,526 @@
+use crate::config::Config;
+use crate::pricing::{self, CostEstimate};
+use crate::session;
+use crate::time::{calculate_elapsed, parse_timestamp};
+use crate::transcript::{
+    parse_mcp_name, relative_path, AgentEntry, BackgroundJob, CompactionState, DiffState, DiffStats, FileSet, FileState, JobStatus, RunningTool, SkillEntry, Status, TodoState, TokenUsage, ToolCall, TranscriptState,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+// ============================================================================
+// JSON Output (`--format json`)
+// ============================================================================
+
+/// Bump on any breaking change to the layout below; additive fields keep
+/// the version. See docs/json-output.md.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct JsonOutput<'a> {
+    pub schema_version: u32,
+    pub session: JsonSession<'a>,
+    pub todos: JsonTodos<'a>,
+    pub skills: Vec<JsonSkill<'a>>,
+    pub agents: Vec<JsonAgent<'a>>,
+    /// Agents of the whole session, oldest first
+    pub agent_log: Vec<JsonAgentRun<'a>>,
+    pub tools: JsonTools<'a>,
+    pub jobs: Vec<JsonJob<'a>>,
+    pub files: JsonFiles<'a>,
+    pub diff: JsonDiff<'a>,
+    pub usage: JsonUsage,
+    pub cost: JsonCost,
+    pub compaction: JsonCompaction<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSession<'a> {
+    pub state: &'static str,
+    pub last_activity: Option<&'a str>,
+    pub error: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonCompaction<'a> {
+    pub count: u32,
+    pub last_time: Option<&'a str>,
+    pub last_trigger: Option<&'a str>,
+    pub last_pre_tokens: Option<u64>,
+    pub since_ms: Option<u64>,
+}
+
+impl<'a> JsonCompaction<'a> {
+    fn new(compaction: &'a CompactionState, now_ms: u64) -> Self {
+        JsonCompaction {
+            count: compaction.count,
+            last_time: compaction.last_time.as_deref(),
+            last_trigger: compaction.last_trigger.as_deref(),
+            last_pre_tokens: compaction.last_pre_tokens,
+            since_ms: compaction
+                .last_time
+                .as_ref()
+                .map(|_| calculate_elapsed(&compaction.last_time, &None, now_ms)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTodos<'a> {
+    pub current: Option<&'a str>,
+    pub done: u32,
+    pub total: u32,
+    pub items: Vec<JsonTodo<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTodo<'a> {
+    pub content: &'a str,
+    pub active_form: Option<&'a str>,
+    pub status: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSkill<'a> {
+    pub name: &'a str,
+    pub status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonAgent<'a> {
+    pub agent_type: &'a str,
+    pub status: &'static str,
+    pub start_time: Option<&'a str>,
+    pub end_time: Option<&'a str>,
+    pub elapsed_secs: u64,
+    pub elapsed_ms: u64,
+    pub agent_id: Option<&'a str>,
+    pub activity: JsonAgentActivity<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonAgentRun<'a> {
+    pub id: &'a str,
+    pub agent_type: &'a str,
+    pub status: &'static str,
+    pub start_time: Option<&'a str>,
+    pub end_time: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonAgentActivity<'a> {
+    pub running: Vec<JsonRunningTool<'a>>,
+    pub completed: BTreeMap<&'a str, u32>,
+    pub todos: JsonTodos<'a>,
+    pub usage: JsonTokens,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTools<'a> {
+    pub running: Vec<JsonRunningTool<'a>>,
+    /// Completions per tool name in the current turn
+    pub completed: BTreeMap<&'a str, u32>,
+    /// Time spent in completed calls per tool name
+    pub durations: BTreeMap<&'a str, JsonToolDuration>,
+    /// Failed calls per tool name in the current turn
+    pub errors: BTreeMap<&'a str, u32>,
+    pub last_error: Option<JsonToolError<'a>>,
+    /// Finished calls of the whole session, oldest first
+    pub log: Vec<JsonToolCall<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonToolCall<'a> {
+    pub name: &'a str,
+    pub target: Option<&'a str>,
+    pub start_time: Option<&'a str>,
+    pub end_time: Option<&'a str>,
+    pub duration_ms: Option<u64>,
+    pub is_error: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonToolError<'a> {
+    pub tool: &'a str,
+    pub message: &'a str,
+    pub timestamp: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonRunningTool<'a> {
+    pub name: &'a str,
+    /// Server and tool of an `mcp__<server>__<tool>` name
+    pub mcp_server: Option<&'a str>,
+    pub mcp_tool: Option<&'a str>,
+    pub target: Option<&'a str>,
+    pub start_time: Option<&'a str>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonToolDuration {
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonJob<'a> {
+    pub id: &'a str,
+    pub command: &'a str,
+    pub status: &'static str,
+    pub exit_code: Option<i32>,
+    pub start_time: Option<&'a str>,
+    pub end_time: Option<&'a str>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonUsage {
+    pub turn: JsonTokens,
+    pub session: JsonTokens,
+    /// Session cache reads over all input tokens; null before any input
+    pub cache_hit_ratio: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonFiles<'a> {
+    pub workspace: Option<&'a str>,
+    pub last: Option<&'a str>,
+    pub turn: JsonFileSet<'a>,
+    pub session: JsonFileSet<'a>,
+}
+
+impl<'a> JsonFiles<'a> {
+    fn new(files: &'a FileState) -> Self {
+        let workspace = files.workspace.as_deref();
+        JsonFiles {
+            workspace,
+            last: files.last.as_deref().map(|p| relative_path(p, workspace)),
+            turn: JsonFileSet::new(&files.turn, workspace),
+            session: JsonFileSet::new(&files.session, workspace),
+        }
+    }
+}
+
+/// Paths relative to the workspace, sorted.
+#[derive(Debug, Serialize)]
+pub struct JsonFileSet<'a> {
+    pub read: Vec<&'a str>,
+    pub created: Vec<&'a str>,
+    pub modified: Vec<&'a str>,
+}
+
+impl<'a> JsonFileSet<'a> {
+    fn new(set: &'a FileSet, workspace: Option<&str>) -> Self {
+        let relative = |paths: &'a BTreeSet<String>| {
+            let mut paths: Vec<&'a str> = paths.iter().map(|p| relative_path(p, workspace)).collect();
+            paths.sort_unstable();
+            paths
+        };
+        JsonFileSet {
+            read: relative(&set.read),
+            created: relative(&set.created),
+            modified: relative(&set.modified),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonDiff<'a> {
+    pub turn: DiffStats,
+    pub session: DiffStats,
+    /// Keyed by path relative to the workspace
+    pub by_file: BTreeMap<&'a str, DiffStats>,
+}
+
+impl<'a> JsonDiff<'a> {
+    fn new(diff: &'a DiffState, workspace: Option<&str>) -> Self {
+        JsonDiff {
+            turn: diff.turn,
+            session: diff.session,
+            by_file: diff
+                .by_file
+                .iter()
+                .map(|(path, stats)| (relative_path(path, workspace), *stats))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTokens {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonCost {
+    pub turn_usd: f64,
+    pub session_usd: f64,
+    pub by_model: BTreeMap<String, JsonModelCost>,
+    /// Models whose tokens are left out of the estimate
+    pub unpriced_models: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonModelCost {
+    pub turn_usd: f64,
+    pub session_usd: f64,
+}
+
+impl From<CostEstimate> for JsonCost {
+    fn from(estimate: CostEstimate) -> Self {
+        JsonCost {
+            turn_usd: estimate.turn_usd,
+            session_usd: estimate.session_usd,
+            by_model: estimate
+                .by_model
+                .into_iter()
+                .map(|(model, (turn_usd, session_usd))| (model, JsonModelCost { turn_usd, session_usd }))
+                .collect(),
+            unpriced_models: estimate.unpriced_models,
+        }
+    }
+}
+
+impl From<&TokenUsage> for JsonTokens {
+    fn from(usage: &TokenUsage) -> Self {
+        JsonTokens {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens,
+            cache_read_input_tokens: usage.cache_read_input_tokens,
+            total: usage.total(),
+        }
+    }
+}
+
+fn status_name(status: &Status) -> &'static str {
+    match status {
+        Status::Running => "running",
+        Status::Completed => "completed",
+        Status::Error => "error",
+    }
+}
+
+impl<'a> JsonOutput<'a> {
+    pub fn new(state: &'a TranscriptState, config: &Config, now_ms: u64) -> Self {
+        JsonOutput {
+            schema_version: SCHEMA_VERSION,
+            session: JsonSession {
+                state: session::effective_state(state, &config.segments.state, now_ms).name(),
+                last_activity: state.session.last_activity.as_deref(),
+                error: state.session.error.as_deref(),
+            },
+            todos: JsonTodos::new(&state.todos),
+            skills: state.skills.iter().map(JsonSkill::new).collect(),
+            agents: state
+                .agents
+                .iter()
+                .map(|a| JsonAgent::new(a, now_ms))
+                .collect(),
+            agent_log: state
+                .agent_log
+                .iter()
+                .map(|a| JsonAgentRun {
+                    id: &a.id,
+                    agent_type: &a.agent_type,
+                    status: status_name(&a.status),
+                    start_time: a.start_time.as_deref(),
+                    end_time: a.end_time.as_deref(),
+                })
+                .collect(),
+            tools: JsonTools {
+                running: state
+                    .tools
+                    .running
+                    .iter()
+                    .map(|t| JsonRunningTool::new(t, now_ms))
+                    .collect(),
+                completed: state
+                    .tools
+                    .completed
+                    .iter()
+                    .map(|(name, count)| (name.as_str(), *count))
+                    .collect(),
+                durations: state
+                    .tools
+                    .durations
+                    .iter()
+                    .map(|(name, d)| {
+                        let duration = JsonToolDuration {
+                            total_ms: d.total_ms,
+                            max_ms: d.max_ms,
+                        };
+                        (name.as_str(), duration)
+                    })
+                    .collect(),
+                errors: state
+                    .tools
+                    .errors
+                    .iter()
+                    .map(|(name, count)| (name.as_str(), *count))
+                    .collect(),
+                last_error: state.tools.last_error.as_ref().map(|e| JsonToolError {
+                    tool: &e.tool,
+                    message: &e.message,
+                    timestamp: e.timestamp.as_deref(),
+                }),
+                log: state.tools.log.iter().map(JsonToolCall::new).collect(),
+            },
+            jobs: state.jobs.iter().map(|j| JsonJob::new(j, now_ms)).collect(),
+            files: JsonFiles::new(&state.files),
+            diff: JsonDiff::new(&state.diff, state.files.workspace.as_deref()),
+            usage: JsonUsage {
+                turn: (&state.usage.turn).into(),
+                session: (&state.usage.session).into(),
+                cache_hit_ratio: state.usage.session.cache_hit_ratio(),
+            },
+            cost: pricing::estimate(&state.usage, config).into(),
+            compaction: JsonCompaction::new(&state.compaction, now_ms),
+        }
+    }
+}
+
+impl<'a> JsonTodos<'a> {
+    fn new(todos: &'a TodoState) -> Self {
+        JsonTodos {
+            current: todos.current.as_deref(),
+            done: todos.done,
+            total: todos.total,
+            items: todos
+                .items
+                .iter()
+                .map(|t| JsonTodo {
+                    content: &t.content,
+                    active_form: t.active_form.as_deref(),
+                    status: &t.status,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> JsonToolCall<'a> {
+    fn new(call: &'a ToolCall) -> Self {
+        let start = call.start_time.as_deref().and_then(parse_timestamp);
+        let end = call.end_time.as_deref().and_then(parse_timestamp);
+        JsonToolCall {
+            name: &call.name,
+            target: call.target.as_deref(),
+            start_time: call.start_time.as_deref(),
+            end_time: call.end_time.as_deref(),
+            duration_ms: start.zip(end).map(|(start, end)| end.saturating_sub(start)),
+            is_error: call.is_error,
+        }
+    }
+}
+
+impl<'a> JsonRunningTool<'a> {
+    fn new(tool: &'a RunningTool, now_ms: u64) -> Self {
+        let mcp = parse_mcp_name(&tool.name);
+        JsonRunningTool {
+            name: &tool.name,
+            mcp_server: mcp.map(|(server, _)| server),
+            mcp_tool: mcp.map(|(_, tool)| tool),
+            target: tool.target.as_deref(),
+            start_time: tool.start_time.as_deref(),
+            elapsed_ms: calculate_elapsed(&tool.start_time, &None, now_ms),
+        }
+    }
+}
+
+impl<'a> JsonJob<'a> {
+    fn new(job: &'a BackgroundJob, now_ms: u64) -> Self {
+        JsonJob {
+            id: &job.id,
+            command: &job.command,
+            status: match job.status {
+                JobStatus::Running => "running",
+                JobStatus::Completed => "completed",
+                JobStatus::Failed => "failed",
+                JobStatus::Killed => "killed",
+            },
+            exit_code: job.exit_code,
+            start_time: job.start_time.as_deref(),
+            end_time: job.end_time.as_deref(),
+            elapsed_ms: calculate_elapsed(&job.start_time, &job.end_time, now_ms),
+        }
+    }
+}
+
+impl<'a> JsonSkill<'a> {
+    fn new(skill: &'a SkillEntry) -> Self {
+        JsonSkill {
+            name: &skill.name,
+            status: status_name(&skill.status),
+        }
+    }
+}
+
+impl<'a> JsonAgent<'a> {
+    fn new(agent: &'a AgentEntry, now_ms: u64) -> Self {
+        let elapsed_ms = calculate_elapsed(&agent.start_time, &agent.end_time, now_ms);
+        JsonAgent {
+            agent_type: &agent.agent_type,
+            status: status_name(&agent.status),
+            start_time: agent.start_time.as_deref(),
+            end_time: agent.end_time.as_deref(),
+            elapsed_secs: elapsed_ms / 1000,
+            elapsed_ms,
+            agent_id: agent.agent_id.as_deref(),
+            activity: JsonAgentActivity {
+                running: agent
+                    .activity
+                    .running
+                    .iter()
+                    .map(|t| JsonRunningTool::new(t, now_ms))
+                    .collect(),
+                completed: agent
+                    .activity
+                    .completed
+                    .iter()
+                    .map(|(name, count)| (name.as_str(), *count))
+                    .collect(),
+                todos: JsonTodos::new(&agent.activity.todos),
+                usage: (&agent.activity.usage).into(),
+            },
+        }
+    }
+}
+
+/// Serialize `state`; `config` supplies price overrides for the cost estimate.
+pub fn format_json(state: &TranscriptState, config: &Config, now_ms: u64) -> String {
+    serde_json::to_string(&JsonOutput::new(state, config, now_ms)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, TURN};
+    use crate::transcript::parse_transcript;
+    use serde_json::Value;
+    use std::fs;
+
+    #[test]
+    fn test_json_output_schema() {
+        let dir = temp_dir("json");
+        let transcript = dir.join("t.jsonl");
+        fs::write(&transcript, TURN).unwrap();
+
+        let state = parse_transcript(&transcript, &Config::default());
+        let value: Value = serde_json::from_str(&format_json(&state, &Config::default(), 0)).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["tools"]["completed"]["Read"], 1);
+        assert_eq!(value["tools"]["running"][0]["name"], "Bash");
+        assert_eq!(value["tools"]["running"][0]["target"], "cargo test");
+        assert_eq!(value["todos"]["total"], 0);
+        assert_eq!(value["session"]["state"], "working");
+        assert!(value["agents"].as_array().unwrap().is_empty());
+        assert_eq!(value["cost"]["session_usd"], 0.0);
+        assert!(value["cost"]["unpriced_models"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use unicode_width::UnicodeWidthChar;
+
+// ============================================================================
+// Width-Aware Layout
+// ============================================================================
+
+/// How much detail each segment shows. `format_output` walks `LEVELS` from
+/// most to least detailed until the line fits the target width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detail {
+    /// Max width of running tool targets; `Some(0)` hides them
+    pub target_width: Option<usize>,
+    /// Max width of the current todo text; `Some(0)` shows counts only
+    pub todo_width: Option<usize>,
+    pub completed_tools: bool,
+    /// Show how long tools have been running or took in total
+    pub durations: bool,
+    /// Show session totals next to the turn's tokens and lines changed
+    pub usage_totals: bool,
+    /// Show the current tool of running agents
+    pub agent_activity: bool,
+    /// Show agents as per-status counts instead of one entry each
+    pub collapse_agents: bool,
+    /// Show background jobs as per-status counts instead of one entry each
+    pub collapse_jobs: bool,
+    /// Show skills as per-status counts instead of one entry each
+    pub collapse_skills: bool,
+    /// Spell out the session state next to its icon
+    pub state_label: bool,
+}
+
+pub const FULL: Detail = Detail {
+    target_width: None,
+    todo_width: None,
+    completed_tools: true,
+    durations: true,
+    usage_totals: true,
+    agent_activity: true,
+    collapse_agents: false,
+    collapse_jobs: false,
+    collapse_skills: false,
+    state_label: true,
+};
+
+/// Shrink steps in priority order: truncate targets first, then drop
+/// completed tools and agent activity, then collapse agents, jobs, and token totals, then
+/// skills, then todo text and the state label.
+pub const LEVELS: &[Detail] = &[
+    FULL,
+    Detail {
+        target_width: Some(15),
+        todo_width: Some(30),
+        ..FULL
+    },
+    Detail {
+        target_width: Some(15),
+        todo_width: Some(30),
+        completed_tools: false,
+        agent_activity: false,
+        ..FULL
+    },
+    Detail {
+        target_width: Some(10),
+        todo_width: Some(20),
+        completed_tools: false,
+        usage_totals: false,
+        agent_activity: false,
+        collapse_agents: true,
+        collapse_jobs: true,
+        ..FULL
+    },
+    Detail {
+        target_width: Some(0),
+        todo_width: Some(12),
+        completed_tools: false,
+        durations: false,
+        usage_totals: false,
+        agent_activity: false,
+        collapse_agents: true,
+        collapse_jobs: true,
+        collapse_skills: true,
+        state_label: true,
+    },
+    Detail {
+        target_width: Some(0),
+        todo_width: Some(0),
+        completed_tools: false,
+        durations: false,
+        usage_totals: false,
+        agent_activity: false,
+        collapse_agents: true,
+        collapse_jobs: true,
+        collapse_skills: true,
+        state_label: false,
+    },
+];
+
+/// Terminal columns taken by `s`, skipping ANSI escape sequences and
+/// counting wide (CJK, emoji) characters as two.
+pub fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            skip_escape(&mut chars);
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+    width
+}
+
+/// Truncate plain text to `max` columns, ending with `…` when shortened.
+pub fn truncate_width(s: &str, max: usize) -> String {
+    if visible_width(s) <= max {
+        return s.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let mut width = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if width + w > max - 1 {
+            break;
+        }
+        out.push(c);
+        width += w;
+    }
+    out.push('…');
+    out
+}
+
+/// Cut a colored line at `max` columns. Escape sequences are kept so colors
+/// stay balanced, and a reset is appended if any were seen.
+pub fn truncate_visible(s: &str, max: usize) -> String {
+    if visible_width(s) <= max {
+        return s.to_string();
+    }
+
+    let mut out = String::new();
+    let mut width = 0;
+    let mut has_escapes = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            has_escapes = true;
+            out.push(c);
+            out.push_str(&skip_escape(&mut chars));
+            continue;
+        }
+        let w = c.width().unwrap_or(0);
+        if width + w > max.saturating_sub(1) {
+            break;
+        }
+        out.push(c);
+        width += w;
+    }
+
+    if max > 0 {
+        out.push('…');
+    }
+    if has_escapes {
+        out.push_str("\x1b[0m");
+    }
+    out
+}
+
+/// Consume a CSI sequence after its ESC, returning the consumed text.
+fn skip_escape(chars: &mut std::str::Chars) -> String {
+    let mut seq = String::new();
+    if chars.clone().next() != Some('[') {
+        return seq;
+    }
+    for c in chars.by_ref() {
+        seq.push(c);
+        if seq.len() > 1 && ('\x40'..='\x7e').contains(&c) {
+            break;
+        }
+    }
+    seq
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_width_ignores_escapes() {
+        assert_eq!(visible_width("\x1b[38;2;1;2;3mabc\x1b[0m"), 3);
+        assert_eq!(visible_width("日本 ok"), 7);
+        assert_eq!(truncate_visible("\x1b[0;37mabcdef\x1b[0m", 4), "\x1b[0;37mabc…\x1b[0m");
+    }
+}
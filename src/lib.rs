@@ -0,0 +1,87 @@
+//! Parse Claude Code JSONL transcripts into a live activity summary.
+//!
+//! The parser replays a transcript as a stream of events (tool_use /
+//! tool_result pairs, Task agents, Skill invocations, TodoWrite lists) and
+//! keeps only the current turn's activity. The formatters turn the result
+//! into the statusline's second line.
+//!
+//! ```no_run
+//! use claude_status::{config::Config, parse_transcript};
+//! use std::path::Path;
+//!
+//! let config = Config::default();
+//! let state = parse_transcript(Path::new("session.jsonl"), &config);
+//! for tool in &state.tools.running {
+//!     println!("{} {:?}", tool.name, tool.target);
+//! }
+//! ```
+//!
+//! For streaming input, feed lines to a [`TranscriptParser`] yourself and
+//! observe [`Event`]s as they happen:
+//!
+//! ```
+//! use claude_status::{config::Config, Event, TranscriptParser};
+//!
+//! let mut parser = TranscriptParser::default();
+//! parser.process_line_with(
+//!     r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"ls"}}]}}"#,
+//!     |event| {
+//!         if let Event::ToolStarted { name, .. } = event {
+//!             assert_eq!(name, "Bash");
+//!         }
+//!     },
+//! );
+//! assert_eq!(parser.snapshot(&Config::default()).tools.running.len(), 1);
+//! ```
+
+pub mod checkpoint;
+pub mod config;
+pub mod dashboard;
+pub mod format;
+pub mod icons;
+pub mod json;
+pub mod layout;
+pub mod pricing;
+pub mod session;
+pub mod sessions;
+pub mod statusline;
+pub mod summary;
+pub mod theme;
+pub mod time;
+pub mod tmux;
+pub mod transcript;
+pub mod watch;
+
+pub use format::{
+    format_agents, format_compaction, format_cost, format_diff, format_files, format_jobs, format_output,
+    format_skills, format_state, format_todos, format_tokens, format_tools,
+};
+pub use session::{effective_state, SessionState, SessionStatus};
+pub use transcript::{
+    extract_target, file_path, parse_mcp_name, parse_reader, parse_transcript,
+    parse_transcript_incremental, relative_path, AgentActivity, AgentEntry, AgentRun, BackgroundJob,
+    CompactionState, DiffState, DiffStats, Event, FileSet, FileState, JobStatus, ModelUsage, RunningTool,
+    SkillEntry, Status, Todo, TodoState, TokenUsage, ToolCall, ToolDuration, ToolError, ToolState,
+    TranscriptFollower, TranscriptParser, TranscriptState, UsageState,
+};
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("claude-status-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    pub const TURN: &str = concat!(
+        r#"{"type":"user","message":{"content":"fix it"}}"#, "\n",
+        r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"a.rs"}}]}}"#, "\n",
+        r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"}]}}"#, "\n",
+        r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"cargo test"}}]}}"#, "\n",
+    );
+}
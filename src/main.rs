@@ -1,666 +1,496 @@
-use serde::Deserialize;
-use serde_json::Value;
-use std::collections::HashMap;
+use claude_status::config::{self, Config};
+use claude_status::icons::Icons;
+use claude_status::theme::Theme;
+use claude_status::time::now_millis;
+use claude_status::{checkpoint, dashboard, json, layout, sessions, statusline, summary, tmux, watch};
+use claude_status::{format_output, parse_transcript, parse_transcript_incremental, TranscriptState};
 use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{Read, Write};
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 // ============================================================================
-// ANSI Colors (Catppuccin Mocha)
-// ============================================================================
-
-const GREEN: &str = "\x1b[38;2;166;227;161m"; // Complete - #a6e3a1
-const YELLOW: &str = "\x1b[38;2;249;226;175m"; // Running - #f9e2af
-const RED: &str = "\x1b[38;2;243;139;168m"; // Error - #f38ba8
-const LAVENDER: &str = "\x1b[38;2;180;190;254m"; // Section icons - #b4befe
-const GRAY: &str = "\x1b[0;37m"; // Separators
-const NC: &str = "\x1b[0m"; // No color (reset)
-
-// Nerd Font icons - status
-const ICON_SPINNER: &str = "\u{f110}";
-const ICON_CHECK: &str = "\u{f00c}";
-const ICON_ERROR: &str = "\u{f00d}";
-
-// Nerd Font icons - sections
-const ICON_TODOS: &str = "\u{f14a}"; // checkbox
-const ICON_AGENTS: &str = "\u{ee0d}"; // robot
-const ICON_TOOLS: &str = "\u{f0ad}"; // wrench
-const ICON_SKILLS: &str = "\u{f0e7}"; // lightning bolt (skills)
-
-// ============================================================================
-// Data Structures
+// Main
 // ============================================================================
 
-#[derive(Debug, Clone, PartialEq)]
-enum Status {
-    Running,
-    Completed,
-    Error,
-}
-
-#[derive(Debug, Clone)]
-struct RunningTool {
-    name: String,
-    target: Option<String>,
-}
-
-#[derive(Debug, Default)]
-struct ToolState {
-    running: Vec<RunningTool>,
-    completed: HashMap<String, u32>,
-}
-
-#[derive(Debug, Clone)]
-struct AgentEntry {
-    agent_type: String,
-    status: Status,
-    start_time: Option<String>,
-    end_time: Option<String>,
-    start_turn: u32, // Track which turn the agent was started in
-}
-
-#[derive(Debug, Clone)]
-struct SkillEntry {
-    name: String,
-    status: Status,
-}
-
-#[derive(Debug, Default)]
-struct TodoState {
-    current: Option<String>,
-    done: u32,
-    total: u32,
+const USAGE: &str = "\
+Usage: claude-status [--width N] [--format text|json|tmux] <transcript_path>
+       claude-status render [--width N] [--format text|json|tmux] < statusline.json
+       claude-status tmux [--width N] [<directory>]
+       claude-status summary [--format text|json] <transcript_path>
+       claude-status watch [--width N] [--format text|json] <transcript_path>
+       claude-status dashboard <transcript_path>
+       claude-status sessions [--all] [--watch] [--format text|json]
+       claude-status check-config";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    /// Text with tmux `#[fg=...]` directives instead of escape sequences
+    Tmux,
 }
 
+/// Command-line options shared by all modes.
 #[derive(Debug, Default)]
-struct TranscriptState {
-    tools: ToolState,
-    agents: Vec<AgentEntry>,
-    skills: Vec<SkillEntry>,
-    todos: TodoState,
+struct CliArgs {
+    positional: Vec<String>,
+    width: Option<usize>,
+    format: OutputFormat,
+    /// `sessions`: include inactive sessions
+    all: bool,
+    /// `sessions`: keep refreshing
+    watch: bool,
 }
 
-// ============================================================================
-// JSONL Parsing
-// ============================================================================
-
-#[derive(Debug, Deserialize)]
-struct TodoItem {
-    status: Option<String>,
-    #[serde(rename = "activeForm")]
-    active_form: Option<String>,
-}
-
-/// Truncate a file path for display, matching claude-hud's logic.
-/// If path > max_len, show `.../<filename>`.
-fn truncate_path(path: &str, max_len: usize) -> String {
-    // Normalize Windows backslashes to forward slashes
-    let normalized = path.replace('\\', "/");
-
-    if normalized.len() <= max_len {
-        return normalized;
-    }
-
-    // Extract filename
-    let filename = normalized.rsplit('/').next().unwrap_or(&normalized);
-
-    // If filename itself is too long, truncate it
-    if filename.len() >= max_len {
-        return format!("{}...", &filename[..max_len.saturating_sub(3)]);
-    }
+fn parse_args(args: impl Iterator<Item = String>) -> Result<CliArgs, String> {
+    let mut cli = CliArgs::default();
+    let mut args = args.peekable();
 
-    format!(".../{}", filename)
-}
-
-fn extract_target(name: &str, input: Option<&Value>) -> Option<String> {
-    let input = input?;
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
 
-    match name {
-        "Read" | "Write" | "Edit" | "NotebookEdit" => {
-            let path = input
-                .get("file_path")
-                .or_else(|| input.get("notebook_path"))
-                .and_then(|v| v.as_str())?;
-            Some(truncate_path(path, 30))
+        match flag.as_str() {
+            "--width" => {
+                let value = inline_value
+                    .or_else(|| args.next())
+                    .ok_or("--width needs a value")?;
+                let width = value
+                    .parse()
+                    .map_err(|_| format!("--width: invalid number \"{}\"", value))?;
+                cli.width = Some(width);
+            }
+            "--format" => {
+                let value = inline_value
+                    .or_else(|| args.next())
+                    .ok_or("--format needs a value")?;
+                cli.format = match value.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    "tmux" => OutputFormat::Tmux,
+                    _ => return Err(format!("--format: expected text, json, or tmux, got \"{}\"", value)),
+                };
+            }
+            "--all" => cli.all = true,
+            "--watch" => cli.watch = true,
+            _ if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ => cli.positional.push(arg),
         }
-        "Glob" => input
-            .get("pattern")
-            .and_then(|v| v.as_str())
-            .map(|s| truncate(s, 20)),
-        "Grep" => input
-            .get("pattern")
-            .and_then(|v| v.as_str())
-            .map(|s| truncate(s, 20)),
-        "Bash" => input
-            .get("command")
-            .and_then(|v| v.as_str())
-            .map(|s| truncate(s, 25)),
-        "Task" => input
-            .get("description")
-            .and_then(|v| v.as_str())
-            .map(|s| truncate(s, 30)),
-        "WebFetch" | "WebSearch" => input
-            .get("url")
-            .or_else(|| input.get("query"))
-            .and_then(|v| v.as_str())
-            .map(|s| truncate(s, 25)),
-        _ => None,
     }
-}
 
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[..max_len - 3])
-    }
+    Ok(cli)
 }
 
-fn update_todos(state: &mut TodoState, todos: &[TodoItem]) {
-    state.total = todos.len() as u32;
-    state.done = todos
-        .iter()
-        .filter(|t| t.status.as_deref() == Some("completed"))
-        .count() as u32;
-    state.current = todos
-        .iter()
-        .find(|t| t.status.as_deref() == Some("in_progress"))
-        .and_then(|t| t.active_form.clone());
+/// Target width: `--width`, then the stdin payload, then `COLUMNS`.
+fn target_width(cli: Option<usize>, payload: Option<usize>) -> Option<usize> {
+    cli.or(payload)
+        .or_else(|| env::var("COLUMNS").ok().and_then(|c| c.trim().parse().ok()))
+        .filter(|w| *w > 0)
 }
 
-// ============================================================================
-// Transcript Parsing
-// ============================================================================
-
-fn parse_transcript(path: &Path) -> TranscriptState {
-    let mut state = TranscriptState::default();
-    let mut tool_starts: HashMap<String, (String, Option<String>)> = HashMap::new();
-    let mut agent_starts: HashMap<String, AgentEntry> = HashMap::new();
-    let mut skill_starts: HashMap<String, SkillEntry> = HashMap::new();
-
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => return state,
-    };
-
-    let reader = BufReader::new(file);
-
-    // Track if we've seen a user message (pending new turn)
-    let mut pending_reset = false;
-    // Track current turn number for agent aging
-    let mut current_turn: u32 = 0;
-
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
-
-        if line.trim().is_empty() {
-            continue;
+fn main() {
+    let cli = match parse_args(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("claude-status: {}", err);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
         }
+    };
 
-        let value: Value = match serde_json::from_str(&line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
+    let Some(command) = cli.positional.first() else {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    };
 
-        let line_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        let timestamp = value.get("timestamp").and_then(|v| v.as_str()).map(String::from);
-
-        // Check if this is an agent-level message (has agentId) vs top-level conversation
-        let is_top_level = value.get("agentId").is_none();
-
-        if line_type == "user" && is_top_level {
-            // Check if this is actually a tool result message (not a real user message)
-            let is_tool_result = value
-                .get("message")
-                .and_then(|m| m.get("content"))
-                .and_then(|c| c.as_array())
-                .map(|arr| {
-                    arr.iter().any(|block| {
-                        block.get("type").and_then(|t| t.as_str()) == Some("tool_result")
-                    })
-                })
-                .unwrap_or(false);
-
-            // Check if this is a meta message (skill content injection, system message, etc.)
-            let is_meta = value.get("isMeta").and_then(|v| v.as_bool()).unwrap_or(false);
-
-            // Check if this is a skill content message (has sourceToolUseID)
-            let is_skill_content = value.get("sourceToolUseID").is_some();
-
-            // Check if this is an agent notification (background task completion)
-            let is_agent_notification = value
-                .get("message")
-                .and_then(|m| m.get("content"))
-                .and_then(|c| c.as_str())
-                .map(|s| s.starts_with("<agent-notification>"))
-                .unwrap_or(false);
-
-            if !is_tool_result && !is_meta && !is_skill_content && !is_agent_notification {
-                pending_reset = true;
-            }
-        }
+    if cli.format == OutputFormat::Tmux
+        && matches!(command.as_str(), "summary" | "watch" | "dashboard" | "sessions" | "check-config")
+    {
+        eprintln!("claude-status: --format tmux only applies to a transcript, render, or tmux");
+        std::process::exit(1);
+    }
 
-        // Reset activity when assistant starts responding (new turn)
-        if line_type == "assistant" && is_top_level && pending_reset {
-            current_turn += 1;
-            tool_starts.clear();
-            // Keep only agents that are BOTH running AND from the current or previous turn
-            // This ensures agents don't persist indefinitely if their tool_result is missing
-            agent_starts.retain(|_, agent| {
-                agent.status == Status::Running && agent.start_turn >= current_turn.saturating_sub(1)
-            });
-            skill_starts.clear();
-            state.tools.completed.clear();
-            state.tools.running.clear();
-            state.agents.clear();
-            state.skills.clear();
-            pending_reset = false;
-        }
+    if command == "check-config" {
+        check_config();
+        return;
+    }
 
-        // Process todos from user messages
-        if let Some(todos) = value.get("todos").and_then(|v| v.as_array()) {
-            let todo_items: Vec<TodoItem> = todos
-                .iter()
-                .filter_map(|v| serde_json::from_value(v.clone()).ok())
-                .collect();
-            update_todos(&mut state.todos, &todo_items);
-        }
+    let (config, config_error) = load_config();
 
-        // Process message content
-        if let Some(content) = value.get("message").and_then(|m| m.get("content")).and_then(|c| c.as_array()) {
-            for block in content {
-                let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-                match block_type {
-                    "tool_use" => {
-                        let id = block.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                        let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                        let input = block.get("input");
-
-                        if id.is_empty() || name.is_empty() {
-                            continue;
-                        }
-
-                        // Handle TodoWrite
-                        if name == "TodoWrite" {
-                            if let Some(input) = input {
-                                if let Some(todos_arr) = input.get("todos").and_then(|v| v.as_array()) {
-                                    let todo_items: Vec<TodoItem> = todos_arr
-                                        .iter()
-                                        .filter_map(|v| serde_json::from_value(v.clone()).ok())
-                                        .collect();
-                                    update_todos(&mut state.todos, &todo_items);
-                                }
-                            }
-                        }
-
-                        // Handle Task (agents)
-                        if name == "Task" {
-                            if let Some(input) = input {
-                                let agent_type = input
-                                    .get("subagent_type")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("agent");
-
-                                agent_starts.insert(
-                                    id.to_string(),
-                                    AgentEntry {
-                                        agent_type: agent_type.to_string(),
-                                        status: Status::Running,
-                                        start_time: timestamp.clone(),
-                                        end_time: None,
-                                        start_turn: current_turn,
-                                    },
-                                );
-                            }
-                        } else if name == "Skill" {
-                            // Handle Skill invocations
-                            // Use skill name as key to deduplicate (only show most recent per skill)
-                            if let Some(input) = input {
-                                let skill_name = input
-                                    .get("skill")
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("skill");
-
-                                // Remove any previous entry for this skill name
-                                skill_starts.retain(|_, entry| entry.name != skill_name);
-
-                                skill_starts.insert(
-                                    id.to_string(),
-                                    SkillEntry {
-                                        name: skill_name.to_string(),
-                                        status: Status::Running,
-                                    },
-                                );
-                            }
-                        } else {
-                            // Regular tool
-                            let target = extract_target(name, input);
-                            tool_starts.insert(id.to_string(), (name.to_string(), target));
-                        }
-                    }
-                    "tool_result" => {
-                        let tool_use_id = block
-                            .get("tool_use_id")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("");
-                        let is_error = block.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                        if tool_use_id.is_empty() {
-                            continue;
-                        }
-
-                        // Check if it's an agent
-                        if let Some(agent) = agent_starts.get_mut(tool_use_id) {
-                            agent.status = if is_error {
-                                Status::Error
-                            } else {
-                                Status::Completed
-                            };
-                            agent.end_time = timestamp.clone();
-                            continue;
-                        }
-
-                        // Check if it's a skill
-                        if let Some(skill) = skill_starts.get_mut(tool_use_id) {
-                            skill.status = if is_error {
-                                Status::Error
-                            } else {
-                                Status::Completed
-                            };
-                            continue;
-                        }
-
-                        // Regular tool - move from running to completed
-                        if let Some((name, _)) = tool_starts.remove(tool_use_id) {
-                            *state.tools.completed.entry(name).or_insert(0) += 1;
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
+    if command == "render" {
+        render(&config, config_error.as_deref(), &cli);
+        return;
     }
 
-    // Convert remaining tool_starts to running tools
-    state.tools.running = tool_starts
-        .into_values()
-        .map(|(name, target)| RunningTool { name, target })
-        .collect();
-
-    // Convert agents
-    state.agents = agent_starts.into_values().collect();
+    if command == "summary" {
+        print_summary(&config, &cli);
+        return;
+    }
 
-    // Convert skills
-    state.skills = skill_starts.into_values().collect();
+    if command == "watch" {
+        watch_transcript(&config, config_error.as_deref(), &cli);
+        return;
+    }
 
-    // Limit to recent entries
-    if state.tools.running.len() > 10 {
-        let len = state.tools.running.len();
-        state.tools.running = state.tools.running.split_off(len - 10);
+    if command == "dashboard" {
+        show_dashboard(&config, &cli);
+        return;
     }
-    if state.agents.len() > 5 {
-        let len = state.agents.len();
-        state.agents = state.agents.split_off(len - 5);
+
+    if command == "sessions" {
+        list_sessions(&config, &cli);
+        return;
     }
-    if state.skills.len() > 3 {
-        let len = state.skills.len();
-        state.skills = state.skills.split_off(len - 3);
+
+    if command == "tmux" {
+        print_for_pane(&config, config_error.as_deref(), &cli);
+        return;
     }
 
-    state
+    let path = std::path::Path::new(command);
+    if !path.exists() {
+        std::process::exit(0);
+    }
+    print_line2(path, &config, config_error.as_deref(), cli.width, cli.format);
 }
 
-// ============================================================================
-// Output Formatting
-// ============================================================================
-
-fn format_output(state: &TranscriptState) -> String {
-    let mut parts: Vec<String> = vec![];
-
-    if let Some(todo_str) = format_todos(&state.todos) {
-        parts.push(todo_str);
+/// Print Line 2 for one transcript.
+fn print_line2(path: &Path, config: &Config, config_error: Option<&str>, width: Option<usize>, format: OutputFormat) {
+    if format == OutputFormat::Json {
+        let config = config.unlimited();
+        println!("{}", json::format_json(&load_state(path, &config), &config, now_millis()));
+        return;
     }
 
-    if let Some(skill_str) = format_skills(&state.skills) {
-        parts.push(skill_str);
-    }
+    let width = target_width(width, None);
+    let theme = theme_for(config, format);
+    let icons = config.icons.set.icons();
+    let output = format_output(&load_state(path, config), config, &theme, &icons, width);
+    let output = with_config_error(output, config_error, &theme, &icons, width);
 
-    if let Some(agent_str) = format_agents(&state.agents) {
-        parts.push(agent_str);
+    if !output.is_empty() {
+        println!("{}", finish_line(output, format));
     }
+}
 
-    if let Some(tool_str) = format_tools(&state.tools) {
-        parts.push(tool_str);
+/// Tmux mode: Line 2 in tmux format for the latest session started in a
+/// directory (default: the current one) or a parent of it, for
+/// `#(claude-status tmux "#{pane_current_path}")` in `status-right`.
+fn print_for_pane(config: &Config, config_error: Option<&str>, cli: &CliArgs) {
+    let dir = match cli.positional.get(1) {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => env::current_dir().unwrap_or_default(),
+    };
+    let transcript = sessions::projects_dir().and_then(|projects| sessions::latest_transcript(&projects, &dir));
+    // No session there: print nothing, so the status line stays clean
+    if let Some(path) = transcript {
+        print_line2(&path, config, config_error, cli.width, OutputFormat::Tmux);
     }
+}
 
-    if parts.is_empty() {
-        return String::new();
+fn theme_for(config: &Config, format: OutputFormat) -> Theme {
+    match format {
+        OutputFormat::Tmux => Theme::for_tmux(config),
+        _ => Theme::from_config(config),
     }
+}
 
-    parts.join(&format!(" {GRAY}|{NC} "))
+/// Convert a rendered line to the output format.
+fn finish_line(line: String, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Tmux => tmux::from_ansi(&line),
+        _ => line,
+    }
 }
 
-fn format_skills(skills: &[SkillEntry]) -> Option<String> {
-    if skills.is_empty() {
-        return None;
+/// Statusline mode: read Claude Code's JSON payload from stdin and print
+/// both lines.
+fn render(config: &Config, config_error: Option<&str>, cli: &CliArgs) {
+    let mut input = String::new();
+    let _ = std::io::stdin().read_to_string(&mut input);
+    let payload = statusline::Payload::parse(&input);
+
+    if cli.format == OutputFormat::Json {
+        let config = config.unlimited();
+        let state = match payload.transcript_path().filter(|p| p.is_file()) {
+            Some(path) => load_state(path, &config),
+            None => TranscriptState::default(),
+        };
+        println!("{}", json::format_json(&state, &config, now_millis()));
+        return;
     }
 
-    let parts: Vec<String> = skills
-        .iter()
-        .map(|s| {
-            let (color, icon) = match s.status {
-                Status::Running => (YELLOW, ICON_SPINNER),
-                Status::Completed => (GREEN, ICON_CHECK),
-                Status::Error => (RED, ICON_ERROR),
-            };
+    // Line 1 already shows Claude Code's cost; the estimate is for when
+    // it's missing, and two different figures side by side only confuse
+    let mut config = config.clone();
+    if payload.total_cost_usd().is_some() {
+        config.segments.cost.enabled = false;
+    }
+    let config = &config;
 
-            format!("{color}{icon}{NC} {}", s.name)
-        })
-        .collect();
+    let width = target_width(cli.width, payload.terminal_width);
+    let theme = theme_for(config, cli.format);
+    let icons = config.icons.set.icons();
 
-    Some(format!("{LAVENDER}{ICON_SKILLS}{NC} {}", parts.join(" ")))
-}
+    let line1 = statusline::format_line1(&payload, &theme);
+    let line1 = match width {
+        Some(width) => layout::truncate_visible(&line1, width),
+        None => line1,
+    };
+    println!("{}", finish_line(line1, cli.format));
 
-fn format_todos(todos: &TodoState) -> Option<String> {
-    if todos.total == 0 {
-        return None;
+    let line2 = match payload.transcript_path().filter(|p| p.is_file()) {
+        Some(path) => format_output(&load_state(path, config), config, &theme, &icons, width),
+        None => String::new(),
+    };
+    let line2 = with_config_error(line2, config_error, &theme, &icons, width);
+    if !line2.is_empty() {
+        println!("{}", finish_line(line2, cli.format));
     }
+}
 
-    let (color, icon) = if todos.done == todos.total {
-        (GREEN, ICON_CHECK)
-    } else {
-        (YELLOW, ICON_SPINNER)
+/// Summary mode: report on a whole session, e.g. a finished one.
+fn print_summary(config: &Config, cli: &CliArgs) {
+    let Some(path) = cli.positional.get(1) else {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
     };
 
-    let text = if let Some(ref current) = todos.current {
-        if todos.done < todos.total {
-            format!(
-                "{LAVENDER}{ICON_TODOS}{NC} {color}{icon}{NC} {current} ({}/{})",
-                todos.done, todos.total
-            )
-        } else {
-            format!(
-                "{LAVENDER}{ICON_TODOS}{NC} {color}{icon}{NC} All done ({}/{})",
-                todos.done, todos.total
-            )
+    match summary::summarize(Path::new(path), config) {
+        Ok(summary) if cli.format == OutputFormat::Json => println!("{}", summary::format_summary_json(&summary)),
+        Ok(summary) => println!("{}", summary::format_summary(&summary)),
+        Err(err) => {
+            eprintln!("claude-status: {}: {}", path, err);
+            std::process::exit(1);
         }
-    } else {
-        format!(
-            "{LAVENDER}{ICON_TODOS}{NC} {color}{icon}{NC} {}/{}",
-            todos.done, todos.total
-        )
-    };
-
-    Some(text)
-}
-
-fn format_agents(agents: &[AgentEntry]) -> Option<String> {
-    if agents.is_empty() {
-        return None;
     }
-
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
-
-    let parts: Vec<String> = agents
-        .iter()
-        .map(|a| {
-            let (color, icon) = match a.status {
-                Status::Running => (YELLOW, ICON_SPINNER),
-                Status::Completed => (GREEN, ICON_CHECK),
-                Status::Error => (RED, ICON_ERROR),
-            };
-
-            let elapsed = calculate_elapsed(&a.start_time, &a.end_time, now);
-            let elapsed_str = if elapsed > 0 {
-                format!(" ({}s)", elapsed)
-            } else {
-                String::new()
-            };
-
-            format!("{color}{icon}{NC} {}{}", a.agent_type, elapsed_str)
-        })
-        .collect();
-
-    Some(format!("{LAVENDER}{ICON_AGENTS}{NC} {}", parts.join(" ")))
 }
 
-fn format_tools(tools: &ToolState) -> Option<String> {
-    let mut parts: Vec<String> = vec![];
-
-    // Check if we have running file operations (these need more space for paths)
-    let has_file_ops = tools.running.iter().any(|t| {
-        matches!(t.name.as_str(), "Read" | "Write" | "Edit" | "NotebookEdit")
-    });
+/// Watch mode: redraw Line 2 in place as the transcript grows, or print a
+/// JSON object per change with `--format json`.
+fn watch_transcript(config: &Config, config_error: Option<&str>, cli: &CliArgs) {
+    let Some(path) = cli.positional.get(1).map(Path::new) else {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    };
+    if !path.is_file() {
+        eprintln!("claude-status: {}: no such file", path.display());
+        std::process::exit(1);
+    }
 
-    // Show fewer completed tools if we have file operations running
-    let max_completed = if has_file_ops { 2 } else { 5 };
+    let json = cli.format == OutputFormat::Json;
+    let json_config = config.unlimited();
+    let theme = Theme::from_config(config);
+    let icons = config.icons.set.icons();
+    let mut stdout = std::io::stdout().lock();
+    let mut last = String::new();
 
-    let mut completed: Vec<_> = tools.completed.iter().collect();
-    completed.sort_by(|a, b| b.1.cmp(a.1));
+    watch::catch_signals();
+    if !json {
+        let _ = write!(stdout, "{}", watch::LINE_ENTER);
+    }
 
-    for (name, count) in completed.iter().take(max_completed) {
-        let suffix = if **count > 1 {
-            format!(" ×{}", count)
+    let result = watch::follow(path, watch::TICK, |follower| {
+        let output = if json {
+            json::format_json(&follower.state(&json_config), &json_config, now_millis())
         } else {
-            String::new()
+            let width = target_width(cli.width, watch::terminal_width());
+            let output = format_output(&follower.state(config), config, &theme, &icons, width);
+            with_config_error(output, config_error, &theme, &icons, width)
         };
-        parts.push(format!("{GREEN}{ICON_CHECK}{NC} {}{}", name, suffix));
-    }
+        if output == last {
+            return Ok(());
+        }
 
-    // Show running tools - file ops first (they have paths)
-    let mut running: Vec<_> = tools.running.iter().collect();
-    running.sort_by_key(|t| {
-        // File operations come first (lower sort key)
-        if matches!(t.name.as_str(), "Read" | "Write" | "Edit" | "NotebookEdit") {
-            0
+        if json {
+            writeln!(stdout, "{}", output)?;
+            stdout.flush()?;
         } else {
-            1
+            watch::redraw_line(&mut stdout, &output)?;
         }
+        last = output;
+        Ok(())
     });
 
-    for tool in running.iter().take(2) {
-        let target = tool
-            .target
-            .as_ref()
-            .map(|t| format!(" {}", t))
-            .unwrap_or_default();
-        parts.push(format!("{YELLOW}{ICON_SPINNER}{NC} {}{}", tool.name, target));
+    if !json {
+        let _ = writeln!(stdout, "{}", watch::LINE_LEAVE);
+        let _ = stdout.flush();
     }
-
-    if parts.is_empty() {
-        None
-    } else {
-        Some(format!("{LAVENDER}{ICON_TOOLS}{NC} {}", parts.join(" ")))
+    if let Err(err) = result {
+        eprintln!("claude-status: {}: {}", path.display(), err);
+        std::process::exit(1);
     }
 }
 
-fn calculate_elapsed(start: &Option<String>, end: &Option<String>, now_secs: u64) -> u64 {
-    let start_secs = start
-        .as_ref()
-        .and_then(|s| parse_timestamp(s))
-        .unwrap_or(now_secs);
+/// Dashboard mode: a full-screen view of the session, redrawn as the
+/// transcript grows. Ctrl-C quits.
+fn show_dashboard(config: &Config, cli: &CliArgs) {
+    let Some(path) = cli.positional.get(1).map(Path::new) else {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    };
+    if !path.is_file() {
+        eprintln!("claude-status: {}: no such file", path.display());
+        std::process::exit(1);
+    }
 
-    let end_secs = end
-        .as_ref()
-        .and_then(|s| parse_timestamp(s))
-        .unwrap_or(now_secs);
+    let config = config.unlimited();
+    let theme = Theme::from_config(&config);
+    let icons = config.icons.set.icons();
+    let title = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let mut stdout = std::io::stdout().lock();
+    let mut last = Vec::new();
+
+    watch::catch_signals();
+    let _ = write!(stdout, "{}", watch::FULL_SCREEN_ENTER);
+
+    let result = watch::follow(path, watch::TICK, |follower| {
+        let size = watch::terminal_size().unwrap_or((80, 24));
+        let state = follower.state(&config);
+        let lines = dashboard::render_dashboard(&state, &config, &theme, &icons, &title, size, now_millis());
+        if lines == last {
+            return Ok(());
+        }
 
-    end_secs.saturating_sub(start_secs)
-}
+        watch::redraw(&mut stdout, &lines)?;
+        last = lines;
+        Ok(())
+    });
 
-fn parse_timestamp(ts: &str) -> Option<u64> {
-    let ts = ts.trim_end_matches('Z');
-    let parts: Vec<&str> = ts.split('T').collect();
-    if parts.len() != 2 {
-        return None;
+    let _ = write!(stdout, "{}", watch::FULL_SCREEN_LEAVE);
+    let _ = stdout.flush();
+    if let Err(err) = result {
+        eprintln!("claude-status: {}: {}", path.display(), err);
+        std::process::exit(1);
     }
+}
 
-    let date_parts: Vec<u32> = parts[0]
-        .split('-')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    let time_str = parts[1].split('.').next()?;
-    let time_parts: Vec<u32> = time_str
-        .split(':')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    if date_parts.len() != 3 || time_parts.len() != 3 {
-        return None;
-    }
+/// Sessions mode: one row per recent transcript under the projects
+/// directory. `--watch` refreshes every second.
+fn list_sessions(config: &Config, cli: &CliArgs) {
+    let Some(projects) = sessions::projects_dir() else {
+        eprintln!("claude-status: cannot determine projects directory (HOME not set)");
+        std::process::exit(1);
+    };
 
-    let days_since_epoch = (date_parts[0] - 1970) as u64 * 365
-        + date_parts[1] as u64 * 30
-        + date_parts[2] as u64;
-    let seconds_in_day =
-        time_parts[0] as u64 * 3600 + time_parts[1] as u64 * 60 + time_parts[2] as u64;
+    let json = cli.format == OutputFormat::Json;
+    let window = (!cli.all).then_some(sessions::ACTIVE_WINDOW);
+    let cache_dir = checkpoint::cache_dir();
+    let theme = Theme::from_config(config);
+    let icons = config.icons.set.icons();
+    let render = || -> std::io::Result<String> {
+        let now = now_millis();
+        let rows = match sessions::scan(&projects, window, cache_dir.as_deref(), config, now) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => vec![],
+            result => result?,
+        };
+        if json {
+            Ok(sessions::format_sessions_json(&rows, config, now))
+        } else if rows.is_empty() {
+            Ok("No sessions active in the last hour (--all lists every session)".to_string())
+        } else {
+            Ok(sessions::format_sessions(&rows, config, &theme, &icons, now))
+        }
+    };
 
-    Some(days_since_epoch * 86400 + seconds_in_day)
-}
+    let result = if !cli.watch {
+        render().map(|output| println!("{}", output))
+    } else {
+        let mut stdout = std::io::stdout().lock();
+        let mut last = String::new();
+        watch::catch_signals();
+        if !json {
+            let _ = write!(stdout, "{}", watch::FULL_SCREEN_ENTER);
+        }
 
-// ============================================================================
-// Main
-// ============================================================================
+        let result = watch::repeat(watch::TICK, || {
+            let output = render()?;
+            if output == last {
+                return Ok(());
+            }
+            if json {
+                writeln!(stdout, "{}", output)?;
+                stdout.flush()?;
+            } else {
+                let (width, height) = watch::terminal_size().unwrap_or((80, 24));
+                let lines: Vec<String> = output.lines().take(height).map(|l| layout::truncate_visible(l, width)).collect();
+                watch::redraw(&mut stdout, &lines)?;
+            }
+            last = output;
+            Ok(())
+        });
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+        if !json {
+            let _ = write!(stdout, "{}", watch::FULL_SCREEN_LEAVE);
+            let _ = stdout.flush();
+        }
+        result
+    };
 
-    if args.len() < 2 {
-        eprintln!("Usage: claude-status <transcript_path>");
+    if let Err(err) = result {
+        eprintln!("claude-status: {}: {}", projects.display(), err);
         std::process::exit(1);
     }
+}
 
-    let path = std::path::Path::new(&args[1]);
-    if !path.exists() {
-        std::process::exit(0);
+fn load_state(path: &Path, config: &Config) -> TranscriptState {
+    match checkpoint::cache_dir() {
+        Some(dir) => parse_transcript_incremental(path, &dir, config),
+        None => parse_transcript(path, config),
     }
+}
 
-    let state = parse_transcript(path);
-    let output = format_output(&state);
+/// Load the config, falling back to defaults on error. The error is printed
+/// to stderr and also returned so it can be shown in the statusline itself,
+/// since Claude Code doesn't surface stderr.
+fn load_config() -> (Config, Option<String>) {
+    match config::load() {
+        Ok(config) => (config, None),
+        Err(err) => {
+            eprintln!("claude-status: invalid config {}", err);
+            (Config::default(), Some(err.message))
+        }
+    }
+}
 
-    if !output.is_empty() {
-        println!("{}", output);
+fn with_config_error(
+    output: String,
+    error: Option<&str>,
+    theme: &Theme,
+    icons: &Icons,
+    width: Option<usize>,
+) -> String {
+    let Some(error) = error else {
+        return output;
+    };
+    let Theme { error: red, separator, reset: nc, .. } = theme;
+    let notice = format!("{red}{} config: {}{nc}", icons.error, layout::truncate_width(error, 60));
+    let output = if output.is_empty() {
+        notice
+    } else {
+        format!("{output} {separator}|{nc} {notice}")
+    };
+    match width {
+        Some(width) => layout::truncate_visible(&output, width),
+        None => output,
     }
 }
 
+fn check_config() {
+    let Some(path) = config::config_path() else {
+        eprintln!("claude-status: cannot determine config path (HOME not set)");
+        std::process::exit(1);
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    #[test]
-    fn test_truncate_path_long() {
-        let path = "testdir/dir1/dir2/dir3/dir4/dir5/longfilenametest.py";
-        let result = truncate_path(path, 30);
-        assert_eq!(result, ".../longfilenametest.py", "Path: {}, Result: {}", path, result);
+    match config::load() {
+        Ok(_) if !path.exists() => println!("{}: not found, using defaults", path.display()),
+        Ok(_) => println!("{}: ok", path.display()),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     }
 }
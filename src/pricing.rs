@@ -0,0 +1,123 @@
+use crate::config::Config;
+use crate::transcript::{TokenUsage, UsageState};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Cost Estimation
+// ============================================================================
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Price {
+    pub input: f64,
+    pub output: f64,
+    /// Cache creation (5-minute TTL)
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+const fn price(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Price {
+    Price { input, output, cache_write, cache_read }
+}
+
+/// Published API prices by model id prefix. More specific prefixes come
+/// first; `[pricing]` in the config file takes precedence over all of them.
+pub const BUILTIN_PRICES: &[(&str, Price)] = &[
+    ("claude-opus-4-5", price(5.0, 25.0, 6.25, 0.50)),
+    ("claude-opus-4", price(15.0, 75.0, 18.75, 1.50)),
+    ("claude-sonnet-4", price(3.0, 15.0, 3.75, 0.30)),
+    ("claude-haiku-4", price(1.0, 5.0, 1.25, 0.10)),
+    ("claude-3-opus", price(15.0, 75.0, 18.75, 1.50)),
+    ("claude-3-7-sonnet", price(3.0, 15.0, 3.75, 0.30)),
+    ("claude-3-5-sonnet", price(3.0, 15.0, 3.75, 0.30)),
+    ("claude-3-5-haiku", price(0.80, 4.0, 1.0, 0.08)),
+    ("claude-3-haiku", price(0.25, 1.25, 0.30, 0.03)),
+];
+
+/// Price for a model id, checking config overrides (longest prefix wins)
+/// before the built-in table.
+pub fn lookup(model: &str, config: &Config) -> Option<Price> {
+    let custom = config
+        .pricing
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, p)| *p);
+
+    custom.or_else(|| {
+        BUILTIN_PRICES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, p)| *p)
+    })
+}
+
+/// Cost of `usage` in USD.
+pub fn cost(usage: &TokenUsage, price: &Price) -> f64 {
+    (usage.input_tokens as f64 * price.input
+        + usage.output_tokens as f64 * price.output
+        + usage.cache_creation_input_tokens as f64 * price.cache_write
+        + usage.cache_read_input_tokens as f64 * price.cache_read)
+        / 1_000_000.0
+}
+
+/// Estimated spend for the current turn and the session.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CostEstimate {
+    pub turn_usd: f64,
+    pub session_usd: f64,
+    /// Per model: (turn, session)
+    pub by_model: BTreeMap<String, (f64, f64)>,
+    /// Models with usage but no known price; their tokens are left out
+    pub unpriced_models: Vec<String>,
+}
+
+pub fn estimate(usage: &UsageState, config: &Config) -> CostEstimate {
+    let mut estimate = CostEstimate::default();
+
+    for (model, model_usage) in &usage.by_model {
+        let Some(price) = lookup(model, config) else {
+            estimate.unpriced_models.push(model.clone());
+            continue;
+        };
+        let turn = cost(&model_usage.turn, &price);
+        let session = cost(&model_usage.session, &price);
+        estimate.turn_usd += turn;
+        estimate.session_usd += session;
+        estimate.by_model.insert(model.clone(), (turn, session));
+    }
+    estimate.unpriced_models.sort();
+
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::ModelUsage;
+
+    #[test]
+    fn test_estimate_uses_model_prices() {
+        let tokens = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 2_000_000,
+        };
+        let mut usage = UsageState::default();
+        for model in ["claude-sonnet-4-5-20250929", "<synthetic>"] {
+            usage.by_model.insert(model.to_string(), ModelUsage { turn: TokenUsage::default(), session: tokens });
+        }
+
+        let mut config = Config::default();
+        let result = estimate(&usage, &config);
+        assert!((result.session_usd - 5.1).abs() < 1e-9, "{}", result.session_usd);
+        assert_eq!(result.turn_usd, 0.0);
+        assert_eq!(result.unpriced_models, vec!["<synthetic>"]);
+
+        config.pricing.insert("claude-sonnet-4-5".to_string(), price(1.0, 1.0, 1.0, 0.0));
+        assert!((estimate(&usage, &config).session_usd - 1.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::config::StateSegment;
+use crate::time::parse_timestamp;
+use crate::transcript::{parse_mcp_name, TranscriptState};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// Session State
+// ============================================================================
+
+/// What the session is doing right now, as far as the transcript tells.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionState {
+    /// The last assistant message finished the turn
+    #[default]
+    Idle,
+    /// Claude is thinking or a tool is running
+    Working,
+    /// A tool that needs approval has been pending without any progress;
+    /// never stored by the parser, only derived by [`effective_state`]
+    AwaitingPermission,
+    /// The user pressed Esc (`[Request interrupted by user]`)
+    Interrupted,
+    /// The last assistant message was an API error
+    ApiError,
+}
+
+impl SessionState {
+    /// Stable lowercase name used in JSON output.
+    pub fn name(self) -> &'static str {
+        match self {
+            SessionState::Idle => "idle",
+            SessionState::Working => "working",
+            SessionState::AwaitingPermission => "awaiting_permission",
+            SessionState::Interrupted => "interrupted",
+            SessionState::ApiError => "api_error",
+        }
+    }
+}
+
+/// Session state as of the last top-level line.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub state: SessionState,
+    /// Timestamp of the first top-level user or assistant line
+    pub started_at: Option<String>,
+    /// Timestamp of the last top-level user or assistant line
+    pub last_activity: Option<String>,
+    /// First line of the API error, for [`SessionState::ApiError`]
+    pub error: Option<String>,
+}
+
+/// Tools that ask for approval under the default permission mode. Read-only
+/// tools (Read, Grep, Glob, ...) run without a prompt.
+fn needs_permission(tool: &str) -> bool {
+    matches!(tool, "Bash" | "Edit" | "MultiEdit" | "Write" | "NotebookEdit" | "WebFetch")
+        || parse_mcp_name(tool).is_some()
+}
+
+/// The session state to display at `now_ms`.
+///
+/// The transcript has no record of permission prompts, so a prompt is
+/// inferred: the session is working, a tool that needs approval is running,
+/// and nothing was written for `permission_after_secs`. A long-running
+/// command looks the same, which is why the threshold is configurable.
+pub fn effective_state(state: &TranscriptState, settings: &StateSegment, now_ms: u64) -> SessionState {
+    let session = &state.session;
+    if session.state != SessionState::Working || settings.permission_after_secs == 0 {
+        return session.state;
+    }
+
+    let gated = state.tools.running.iter().any(|t| needs_permission(&t.name));
+    let quiet_ms = session
+        .last_activity
+        .as_deref()
+        .and_then(parse_timestamp)
+        .map(|last| now_ms.saturating_sub(last));
+
+    match quiet_ms {
+        Some(ms) if gated && ms >= settings.permission_after_secs * 1000 => SessionState::AwaitingPermission,
+        _ => SessionState::Working,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::TranscriptParser;
+
+    #[test]
+    fn test_session_state_transitions() {
+        let config = crate::config::Config::default();
+        let settings = &config.segments.state;
+        let mut parser = TranscriptParser::default();
+        let mut state_after = |line: &str| {
+            parser.process_line(line);
+            parser.snapshot(&config)
+        };
+
+        let state = state_after(r#"{"type":"user","timestamp":"2025-01-15T10:30:00Z","message":{"content":"deploy it"}}"#);
+        assert_eq!(state.session.state, SessionState::Working);
+
+        let state = state_after(
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:02Z","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"./deploy.sh"}}]}}"#,
+        );
+        let start = parse_timestamp("2025-01-15T10:30:02Z").unwrap();
+        assert_eq!(effective_state(&state, settings, start + 1000), SessionState::Working);
+        assert_eq!(effective_state(&state, settings, start + 60_000), SessionState::AwaitingPermission);
+
+        let state = state_after(r#"{"type":"user","message":{"content":[{"type":"text","text":"[Request interrupted by user for tool use]"}]}}"#);
+        assert_eq!(state.session.state, SessionState::Interrupted);
+        assert!(state.tools.running.is_empty());
+
+        // A cancelled agent is finished, a background shell keeps running
+        state_after(r#"{"type":"user","message":{"content":"look around"}}"#);
+        state_after(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"b1","name":"Bash","input":{"command":"npm run dev","run_in_background":true}}]}}"#,
+        );
+        state_after(r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"b1","content":"Command running in background with ID: bash_1"}]},"toolUseResult":{"backgroundTaskId":"bash_1"}}"#);
+        state_after(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"task1","name":"Task","input":{"subagent_type":"Explore","prompt":"find it"}}]}}"#,
+        );
+        let state = state_after(
+            r#"{"type":"user","timestamp":"2025-01-15T10:31:00Z","message":{"content":[{"type":"text","text":"[Request interrupted by user for tool use]"}]}}"#,
+        );
+        assert_eq!(state.session.state, SessionState::Interrupted);
+        assert!(state.agents.iter().all(|a| a.status != crate::transcript::Status::Running));
+        let run = state.agent_log.last().unwrap();
+        assert_eq!(run.status, crate::transcript::Status::Error);
+        assert_eq!(run.end_time.as_deref(), Some("2025-01-15T10:31:00Z"));
+        assert_eq!(state.jobs.len(), 1);
+
+        state_after(r#"{"type":"user","message":{"content":"try again"}}"#);
+        let state = state_after(
+            r#"{"type":"assistant","isApiErrorMessage":true,"message":{"content":[{"type":"text","text":"API Error: 529 Overloaded\nretry later"}]}}"#,
+        );
+        assert_eq!(state.session.state, SessionState::ApiError);
+        assert_eq!(state.session.error.as_deref(), Some("API Error: 529 Overloaded"));
+
+        state_after(r#"{"type":"user","message":{"content":"hello"}}"#);
+        // Text streamed before the turn ends has no stop_reason yet
+        let state = state_after(r#"{"type":"assistant","message":{"stop_reason":null,"content":[{"type":"text","text":"Let me check."}]}}"#);
+        assert_eq!(state.session.state, SessionState::Working);
+        let state = state_after(r#"{"type":"assistant","message":{"stop_reason":"end_turn","content":[{"type":"text","text":"Hi!"}]}}"#);
+        assert_eq!(state.session.state, SessionState::Idle);
+    }
+}
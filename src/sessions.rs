@@ -0,0 +1,341 @@
+use crate::config::Config;
+use crate::format::format_state;
+use crate::icons::Icons;
+use crate::json::SCHEMA_VERSION;
+use crate::layout::{self, visible_width};
+use crate::session::{self, SessionState};
+use crate::theme::Theme;
+use crate::time::{format_duration, parse_timestamp};
+use crate::transcript::{parse_transcript, parse_transcript_incremental, Status, TranscriptState};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+// ============================================================================
+// Session Overview (`claude-status sessions`)
+// ============================================================================
+
+/// Transcripts written to within this window count as active.
+pub const ACTIVE_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Columns for the current todo.
+const TODO_WIDTH: usize = 40;
+
+/// Where Claude Code keeps transcripts: `$CLAUDE_CONFIG_DIR/projects`,
+/// then `~/.claude/projects`.
+pub fn projects_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("CLAUDE_CONFIG_DIR").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir).join("projects"));
+    }
+    env::var_os("HOME")
+        .filter(|v| !v.is_empty())
+        .map(|home| PathBuf::from(home).join(".claude").join("projects"))
+}
+
+/// One session transcript and its parsed state.
+#[derive(Debug, Clone)]
+pub struct SessionRow {
+    pub path: PathBuf,
+    /// Directory under `projects/`, e.g. `-home-me-app`
+    pub project: String,
+    /// File modification time, milliseconds since the Unix epoch
+    pub modified_ms: u64,
+    pub state: TranscriptState,
+}
+
+impl SessionRow {
+    /// Last path component of the session's working directory, or the
+    /// project directory name before the first `cwd` is seen.
+    pub fn name(&self) -> &str {
+        match self.state.files.workspace.as_deref() {
+            Some(workspace) => workspace.trim_end_matches('/').rsplit('/').next().unwrap_or(workspace),
+            None => &self.project,
+        }
+    }
+
+    /// First 8 characters of the session id (the file stem), enough to
+    /// tell sessions of one project apart.
+    pub fn short_id(&self) -> String {
+        let stem = self.path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        stem.chars().take(8).collect()
+    }
+
+    /// Milliseconds since the last top-level line, or since the file was
+    /// last written when there is none.
+    pub fn idle_ms(&self, now_ms: u64) -> u64 {
+        let last = self.state.session.last_activity.as_deref().and_then(parse_timestamp);
+        now_ms.saturating_sub(last.unwrap_or(self.modified_ms))
+    }
+}
+
+/// Find session transcripts under `projects`, newest first. Only those
+/// modified within `window` are parsed, or all of them without one.
+/// Checkpoints in `cache_dir` are used for transcripts active within
+/// [`ACTIVE_WINDOW`] only, so listing old sessions doesn't leave a cache
+/// file behind for each of them.
+pub fn scan(
+    projects: &Path,
+    window: Option<Duration>,
+    cache_dir: Option<&Path>,
+    config: &Config,
+    now_ms: u64,
+) -> io::Result<Vec<SessionRow>> {
+    let cutoff = window.map(|w| now_ms.saturating_sub(w.as_millis() as u64));
+    let active_since = now_ms.saturating_sub(ACTIVE_WINDOW.as_millis() as u64);
+    let mut rows = vec![];
+
+    for project in fs::read_dir(projects)? {
+        let project = project?;
+        if !project.file_type()?.is_dir() {
+            continue;
+        }
+
+        for (path, modified_ms) in transcripts(&project.path()) {
+            if cutoff.is_some_and(|cutoff| modified_ms < cutoff) {
+                continue;
+            }
+            let state = match cache_dir {
+                Some(dir) if modified_ms >= active_since => parse_transcript_incremental(&path, dir, config),
+                _ => parse_transcript(&path, config),
+            };
+            rows.push(SessionRow {
+                path,
+                project: project.file_name().to_string_lossy().into_owned(),
+                modified_ms,
+                state,
+            });
+        }
+    }
+
+    rows.sort_by_key(|row| std::cmp::Reverse(row.modified_ms));
+    Ok(rows)
+}
+
+/// Session transcripts in one project directory with their modification
+/// times. Subagent transcripts (`agent-*.jsonl`) are skipped.
+fn transcripts(project: &Path) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(project) else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with("agent-"))
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "jsonl"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            Some((entry.path(), modified_ms))
+        })
+        .collect()
+}
+
+/// Claude Code's directory name for a working directory: every character
+/// other than ASCII letters and digits becomes `-`, so `/home/me/my.app`
+/// is `-home-me-my-app`.
+pub fn project_dir_name(cwd: &Path) -> String {
+    cwd.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// The most recently written transcript of a session started in `cwd`, or
+/// in the closest parent directory that has one, so a shell in a
+/// subdirectory of the project still finds it.
+pub fn latest_transcript(projects: &Path, cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors().find_map(|dir| {
+        transcripts(&projects.join(project_dir_name(dir)))
+            .into_iter()
+            .max_by_key(|(_, modified_ms)| *modified_ms)
+            .map(|(path, _)| path)
+    })
+}
+
+/// One aligned row per session: name, short id, time since last activity,
+/// state, current todo, and running agents.
+pub fn format_sessions(rows: &[SessionRow], config: &Config, theme: &Theme, icons: &Icons, now_ms: u64) -> String {
+    let Theme { accent, separator, reset: nc, .. } = theme;
+
+    let mut table = vec![["PROJECT", "SESSION", "ACTIVE", "STATE", "TODO", "AGENTS"].map(|h| format!("{separator}{h}{nc}"))];
+    for row in rows {
+        let state = &row.state;
+        let current = session::effective_state(state, &config.segments.state, now_ms);
+        let state_text = match current {
+            SessionState::Idle if state.session.last_activity.is_none() => format!("{separator}empty{nc}"),
+            _ => format_state(current, state.session.error.as_deref(), theme, icons, &layout::FULL).unwrap_or_default(),
+        };
+
+        let todos = &state.todos;
+        let todo = match todos.current.as_deref() {
+            Some(current) if todos.done < todos.total => {
+                format!("{} ({}/{})", layout::truncate_width(current, TODO_WIDTH), todos.done, todos.total)
+            }
+            _ if todos.total > 0 => format!("{}/{}", todos.done, todos.total),
+            _ => "-".to_string(),
+        };
+
+        let agents: Vec<&str> = running_agents(state).collect();
+        let agents = if agents.is_empty() { "-".to_string() } else { agents.join(", ") };
+
+        table.push([
+            format!("{accent}{}{nc}", row.name()),
+            row.short_id(),
+            format!("{} ago", format_duration(row.idle_ms(now_ms))),
+            state_text,
+            todo,
+            agents,
+        ]);
+    }
+
+    let mut widths = [0; 6];
+    for cells in &table {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(visible_width(cell));
+        }
+    }
+
+    table
+        .iter()
+        .map(|cells| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - visible_width(cell))))
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn running_agents(state: &TranscriptState) -> impl Iterator<Item = &str> {
+    state
+        .agents
+        .iter()
+        .filter(|a| a.status == Status::Running)
+        .map(|a| a.agent_type.as_str())
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSessions<'a> {
+    schema_version: u32,
+    sessions: Vec<JsonSessionRow<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSessionRow<'a> {
+    path: String,
+    project: &'a str,
+    workspace: Option<&'a str>,
+    modified_ms: u64,
+    last_activity: Option<&'a str>,
+    idle_ms: u64,
+    state: &'static str,
+    error: Option<&'a str>,
+    current_todo: Option<&'a str>,
+    todos_done: u32,
+    todos_total: u32,
+    running_agents: Vec<&'a str>,
+}
+
+/// The rows as one JSON object, see docs/json-output.md.
+pub fn format_sessions_json(rows: &[SessionRow], config: &Config, now_ms: u64) -> String {
+    let sessions = rows
+        .iter()
+        .map(|row| {
+            let state = &row.state;
+            JsonSessionRow {
+                path: row.path.display().to_string(),
+                project: &row.project,
+                workspace: state.files.workspace.as_deref(),
+                modified_ms: row.modified_ms,
+                last_activity: state.session.last_activity.as_deref(),
+                idle_ms: row.idle_ms(now_ms),
+                state: session::effective_state(state, &config.segments.state, now_ms).name(),
+                error: state.session.error.as_deref(),
+                current_todo: state.todos.current.as_deref(),
+                todos_done: state.todos.done,
+                todos_total: state.todos.total,
+                running_agents: running_agents(state).collect(),
+            }
+        })
+        .collect();
+
+    serde_json::to_string(&JsonSessions {
+        schema_version: SCHEMA_VERSION,
+        sessions,
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icons::IconSet;
+    use crate::test_support::{temp_dir, TURN};
+    use crate::theme::{builtin, ColorMode};
+    use crate::time::now_millis;
+    use std::fs::File;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_scan_lists_recent_sessions() {
+        let projects = temp_dir("sessions");
+        for project in ["-repo-app", "-repo-old"] {
+            fs::create_dir(projects.join(project)).unwrap();
+        }
+        let app = concat!(
+            r#"{"type":"user","cwd":"/repo/app","message":{"content":"review"}}"#, "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Task","input":{"subagent_type":"Explore","prompt":"look"}}]}}"#, "\n",
+        );
+        fs::write(projects.join("-repo-app/s1.jsonl"), app).unwrap();
+        fs::write(projects.join("-repo-app/agent-a1.jsonl"), TURN).unwrap();
+        fs::write(projects.join("-repo-old/s2.jsonl"), TURN).unwrap();
+        let day_ago = SystemTime::now() - Duration::from_secs(24 * 60 * 60);
+        File::options()
+            .write(true)
+            .open(projects.join("-repo-old/s2.jsonl"))
+            .unwrap()
+            .set_modified(day_ago)
+            .unwrap();
+
+        let config = Config::default();
+        let now = now_millis();
+        let rows = scan(&projects, Some(ACTIVE_WINDOW), None, &config, now).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].name(), "app");
+        assert_eq!(running_agents(&rows[0].state).collect::<Vec<_>>(), vec!["Explore"]);
+
+        let cache = temp_dir("sessions-cache");
+        let rows = scan(&projects, None, Some(&cache), &config, now).unwrap();
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 1, "only the active session is cached");
+        assert_eq!(rows.iter().map(|r| r.project.as_str()).collect::<Vec<_>>(), vec!["-repo-app", "-repo-old"]);
+
+        let theme = Theme::new(&builtin("catppuccin-mocha").unwrap(), ColorMode::None);
+        let text = format_sessions(&rows, &config, &theme, &IconSet::Ascii.icons(), now);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "PROJECT    SESSION  ACTIVE      STATE        TODO  AGENTS");
+        assert!(lines[1].starts_with("app        s1       "), "{text}");
+        assert!(lines[1].ends_with("... working  -     Explore"), "{text}");
+        assert!(lines[2].starts_with("-repo-old  s2       24h00m ago"), "{text}");
+
+        let latest = latest_transcript(&projects, Path::new("/repo/app/src")).unwrap();
+        assert_eq!(latest, projects.join("-repo-app/s1.jsonl"));
+        assert_eq!(latest_transcript(&projects, Path::new("/elsewhere")), None);
+        assert_eq!(project_dir_name(Path::new("/home/me/my.app")), "-home-me-my-app");
+
+        let json: serde_json::Value = serde_json::from_str(&format_sessions_json(&rows, &config, now)).unwrap();
+        assert_eq!(json["sessions"][0]["workspace"], "/repo/app");
+        assert_eq!(json["sessions"][0]["state"], "working");
+        assert_eq!(json["sessions"][1]["running_agents"].as_array().unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::theme::Theme;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ============================================================================
+// Statusline Payload (stdin JSON from Claude Code)
+// ============================================================================
+
+const BAR_WIDTH: usize = 15;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Payload {
+    #[serde(default)]
+    pub model: Option<Model>,
+    #[serde(default)]
+    pub workspace: Option<Workspace>,
+    #[serde(default)]
+    pub transcript_path: Option<String>,
+    #[serde(default)]
+    pub context_window: Option<ContextWindow>,
+    #[serde(default)]
+    pub cost: Option<Cost>,
+    /// Not sent by Claude Code today; lets wrappers pass the pane width
+    #[serde(default)]
+    pub terminal_width: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Model {
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Workspace {
+    pub current_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ContextWindow {
+    pub used_percentage: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Cost {
+    pub total_cost_usd: Option<f64>,
+}
+
+impl Payload {
+    /// Parse the payload, treating malformed input as empty so the
+    /// statusline still renders something.
+    pub fn parse(input: &str) -> Self {
+        serde_json::from_str(input).unwrap_or_default()
+    }
+
+    pub fn current_dir(&self) -> Option<&str> {
+        self.workspace.as_ref().and_then(|w| w.current_dir.as_deref())
+    }
+
+    /// Claude Code's own running cost of the session.
+    pub fn total_cost_usd(&self) -> Option<f64> {
+        self.cost.as_ref().and_then(|c| c.total_cost_usd)
+    }
+
+    pub fn transcript_path(&self) -> Option<&Path> {
+        self.transcript_path
+            .as_deref()
+            .filter(|p| !p.is_empty())
+            .map(Path::new)
+    }
+}
+
+// ============================================================================
+// Line 1: context, cost, git, directory, model
+// ============================================================================
+
+pub fn format_line1(payload: &Payload, theme: &Theme) -> String {
+    let Theme { separator, branch: branch_color, directory, model: model_color, reset: nc, .. } = theme;
+
+    let percent = payload
+        .context_window
+        .as_ref()
+        .and_then(|c| c.used_percentage)
+        .unwrap_or(0.0)
+        .max(0.0) as usize;
+
+    let filled = (percent * BAR_WIDTH / 100).min(BAR_WIDTH);
+    let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+
+    let mut line = format!("{separator}{bar}{nc} {percent}%");
+
+    if let Some(cost) = payload.total_cost_usd() {
+        line.push_str(&format!(" {separator}(${cost:.4}){nc}"));
+    }
+
+    let current_dir = payload.current_dir().unwrap_or("");
+
+    if let Some(branch) = git_branch(Path::new(current_dir)) {
+        line.push_str(&format!(" {separator}|{nc} {branch_color}{branch}{nc}"));
+    }
+
+    let dir_name = Path::new(current_dir)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| current_dir.to_string());
+    let model = payload
+        .model
+        .as_ref()
+        .and_then(|m| m.display_name.as_deref())
+        .unwrap_or("");
+
+    line.push_str(&format!(" {separator}|{nc} {directory}{dir_name}{nc} {separator}|{nc} {model_color}{model}{nc}"));
+    line
+}
+
+// ============================================================================
+// Git
+// ============================================================================
+
+/// Current branch name read straight from `.git/HEAD`, without spawning git.
+/// Returns `"detached"` for a detached HEAD and `None` outside a repository.
+fn git_branch(start: &Path) -> Option<String> {
+    let git_dir = find_git_dir(start)?;
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+
+    match head.trim().strip_prefix("ref: ") {
+        Some(reference) => Some(
+            reference
+                .strip_prefix("refs/heads/")
+                .unwrap_or(reference)
+                .to_string(),
+        ),
+        None => Some("detached".to_string()),
+    }
+}
+
+/// Walk up from `start` looking for `.git`, following `gitdir:` files used by
+/// worktrees and submodules.
+fn find_git_dir(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(".git");
+        if candidate.is_dir() {
+            return Some(candidate);
+        }
+        if candidate.is_file() {
+            let contents = fs::read_to_string(&candidate).ok()?;
+            let gitdir = contents.trim().strip_prefix("gitdir:")?.trim();
+            return Some(dir.join(gitdir));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_line1_from_payload() {
+        let payload = Payload::parse(
+            r#"{"model":{"display_name":"Opus"},"workspace":{"current_dir":"/nonexistent/my-project"},"context_window":{"used_percentage":42.9},"cost":{"total_cost_usd":0.02341}}"#,
+        );
+        let line = format_line1(&payload, &Theme::default());
+        let (gray, blue, cyan, nc) = ("\x1b[0;37m", "\x1b[0;34m", "\x1b[0;36m", "\x1b[0m");
+        assert!(line.starts_with(&format!("{gray}██████░░░░░░░░░{nc} 42% {gray}($0.0234){nc}")));
+        assert!(line.ends_with(&format!("{blue}my-project{nc} {gray}|{nc} {cyan}Opus{nc}")));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::config::Config;
+use crate::format::{format_token_count, format_usd};
+use crate::json::{JsonTokens, SCHEMA_VERSION};
+use crate::pricing;
+use crate::time::{calculate_elapsed, format_duration};
+use crate::transcript::{DiffStats, Event, FileSet, Status, TokenUsage, TranscriptParser};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+// ============================================================================
+// Session Summary (`claude-status summary`)
+// ============================================================================
+
+/// What a whole session did, built from the parser's event stream rather
+/// than its per-turn state.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SessionSummary {
+    pub schema_version: u32,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub turns: u32,
+    pub compactions: u32,
+    /// Calls and failures per tool name
+    pub tools: BTreeMap<String, ToolSummary>,
+    /// In the order they were started
+    pub agents: Vec<AgentSummary>,
+    /// Invocations per skill name
+    pub skills: BTreeMap<String, u32>,
+    /// Absolute paths, as in [`crate::transcript::FileState::session`]
+    pub files: FileSet,
+    /// Lines added and removed by edits
+    pub lines: DiffStats,
+    pub todos: TodoSummary,
+    #[serde(serialize_with = "serialize_tokens")]
+    pub usage: TokenUsage,
+    pub cost_usd: f64,
+    /// Models with usage but no price; not included in `cost_usd`
+    pub unpriced_models: Vec<String>,
+}
+
+/// Token counts plus their `total`, as in the statusline JSON.
+fn serialize_tokens<S: Serializer>(usage: &TokenUsage, serializer: S) -> Result<S::Ok, S::Error> {
+    JsonTokens::from(usage).serialize(serializer)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolSummary {
+    pub calls: u32,
+    pub errors: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AgentSummary {
+    pub agent_type: String,
+    pub status: &'static str,
+    /// `None` while the agent never finished or lacks timestamps
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TodoSummary {
+    /// Completed todos in the last list
+    pub completed: u32,
+    /// Size of the last todo list
+    pub total: u32,
+}
+
+/// Accumulates a [`SessionSummary`] from parser events.
+#[derive(Debug, Default)]
+struct SummaryBuilder {
+    summary: SessionSummary,
+    /// tool_use id → name until the result arrives
+    tools: HashMap<String, String>,
+    /// Task id → (index into `summary.agents`, start time)
+    agents: HashMap<String, (usize, Option<String>)>,
+}
+
+impl SummaryBuilder {
+    fn record(&mut self, event: Event) {
+        let summary = &mut self.summary;
+        match event {
+            Event::TurnStarted { turn } => summary.turns = turn,
+            Event::ToolStarted { id, name, .. } => {
+                summary.tools.entry(name.clone()).or_default().calls += 1;
+                self.tools.insert(id, name);
+            }
+            Event::ToolFinished { id, is_error: true, .. } => {
+                if let Some(name) = self.tools.remove(&id) {
+                    summary.tools.entry(name).or_default().errors += 1;
+                }
+            }
+            Event::ToolFinished { id, .. } => {
+                self.tools.remove(&id);
+            }
+            Event::AgentStarted { id, agent_type, timestamp } => {
+                self.agents.insert(id, (summary.agents.len(), timestamp));
+                summary.agents.push(AgentSummary {
+                    agent_type,
+                    status: "running",
+                    duration_ms: None,
+                });
+            }
+            Event::AgentFinished { id, status, timestamp, .. } => {
+                let Some((index, start)) = self.agents.remove(&id) else {
+                    return;
+                };
+                let agent = &mut summary.agents[index];
+                agent.status = match status {
+                    Status::Running => "running",
+                    Status::Completed => "completed",
+                    Status::Error => "error",
+                };
+                if start.is_some() && timestamp.is_some() {
+                    agent.duration_ms = Some(calculate_elapsed(&start, &timestamp, 0));
+                }
+            }
+            Event::SkillStarted { name, .. } => *summary.skills.entry(name).or_insert(0) += 1,
+            Event::TodosUpdated { done, total, .. } => {
+                summary.todos = TodoSummary { completed: done, total };
+            }
+            Event::Compacted { count, .. } => summary.compactions = count,
+            _ => {}
+        }
+    }
+}
+
+/// Summarize transcript lines from any reader.
+pub fn summarize_reader(reader: impl BufRead, config: &Config) -> SessionSummary {
+    let mut parser = TranscriptParser::default();
+    let mut builder = SummaryBuilder::default();
+
+    for line in reader.lines().map_while(Result::ok) {
+        parser.process_line_with(&line, |event| builder.record(event));
+    }
+
+    let state = parser.finish(config);
+    let estimate = pricing::estimate(&state.usage, config);
+    let session = state.session;
+
+    let mut summary = builder.summary;
+    summary.schema_version = SCHEMA_VERSION;
+    summary.duration_ms = match (&session.started_at, &session.last_activity) {
+        (Some(_), Some(_)) => Some(calculate_elapsed(&session.started_at, &session.last_activity, 0)),
+        _ => None,
+    };
+    summary.start_time = session.started_at;
+    summary.end_time = session.last_activity;
+    summary.files = state.files.session;
+    summary.lines = state.diff.session;
+    summary.usage = state.usage.session;
+    summary.cost_usd = estimate.session_usd;
+    summary.unpriced_models = estimate.unpriced_models;
+    summary
+}
+
+/// Summarize the transcript at `path`.
+pub fn summarize(path: &Path, config: &Config) -> io::Result<SessionSummary> {
+    let file = File::open(path)?;
+    Ok(summarize_reader(BufReader::new(file), config))
+}
+
+/// `Read ×3, Edit ×2 (1 failed)`, most used first.
+fn format_tool_counts(tools: &BTreeMap<String, ToolSummary>) -> String {
+    let mut tools: Vec<_> = tools.iter().collect();
+    tools.sort_by_key(|(_, t)| std::cmp::Reverse(t.calls));
+    tools
+        .iter()
+        .map(|(name, t)| match t.errors {
+            0 => format!("{} ×{}", name, t.calls),
+            errors => format!("{} ×{} ({} failed)", name, t.calls, errors),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Plain-text report, one labeled line per topic. Topics with nothing to
+/// report are left out.
+pub fn format_summary(summary: &SessionSummary) -> String {
+    let mut lines: Vec<(&str, String)> = vec![];
+
+    let mut session = match (&summary.start_time, &summary.end_time) {
+        (Some(start), Some(end)) => format!("{} → {}", start, end),
+        _ => "no timestamps".to_string(),
+    };
+    if let Some(ms) = summary.duration_ms {
+        session.push_str(&format!(" ({})", format_duration(ms)));
+    }
+    session.push_str(&format!(", {} turns", summary.turns));
+    if summary.compactions > 0 {
+        session.push_str(&format!(", {} compactions", summary.compactions));
+    }
+    lines.push(("Session", session));
+
+    if !summary.tools.is_empty() {
+        lines.push(("Tools", format_tool_counts(&summary.tools)));
+    }
+
+    if !summary.agents.is_empty() {
+        let agents = summary
+            .agents
+            .iter()
+            .map(|a| match (a.status, a.duration_ms) {
+                ("completed", Some(ms)) => format!("{} {}", a.agent_type, format_duration(ms)),
+                (status, Some(ms)) => format!("{} {} ({})", a.agent_type, format_duration(ms), status),
+                (status, None) => format!("{} ({})", a.agent_type, status),
+            })
+            .collect::<Vec<_>>();
+        lines.push(("Agents", agents.join(", ")));
+    }
+
+    if !summary.skills.is_empty() {
+        let skills: Vec<String> = summary
+            .skills
+            .iter()
+            .map(|(name, count)| match count {
+                1 => name.clone(),
+                n => format!("{} ×{}", name, n),
+            })
+            .collect();
+        lines.push(("Skills", skills.join(", ")));
+    }
+
+    let files = &summary.files;
+    for (label, paths) in [("Read", &files.read), ("Created", &files.created), ("Modified", &files.modified)] {
+        if !paths.is_empty() {
+            let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+            let noun = if paths.len() == 1 { "file" } else { "files" };
+            lines.push((label, format!("{} {}: {}", paths.len(), noun, paths.join(", "))));
+        }
+    }
+
+    if !summary.lines.is_empty() {
+        lines.push(("Lines", format!("+{} −{}", summary.lines.added, summary.lines.removed)));
+    }
+
+    if summary.todos.total > 0 {
+        lines.push(("Todos", format!("{}/{} completed", summary.todos.completed, summary.todos.total)));
+    }
+
+    if summary.usage.total() > 0 {
+        let mut tokens = format_token_count(summary.usage.total());
+        if let Some(ratio) = summary.usage.cache_hit_ratio() {
+            tokens.push_str(&format!(" ({:.0}% cached)", ratio * 100.0));
+        }
+        lines.push(("Tokens", tokens));
+
+        let mut cost = format_usd(summary.cost_usd);
+        if !summary.unpriced_models.is_empty() {
+            cost.push_str(&format!(" (no price for {})", summary.unpriced_models.join(", ")));
+        }
+        lines.push(("Cost", cost));
+    }
+
+    let width = lines.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    lines
+        .iter()
+        .map(|(label, text)| format!("{:width$}  {}", label, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// JSON form of the summary; see docs/json-output.md.
+pub fn format_summary_json(summary: &SessionSummary) -> String {
+    serde_json::to_string(summary).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_spans_turns() {
+        let transcript = concat!(
+            r#"{"type":"user","timestamp":"2025-01-15T10:00:00Z","message":{"content":"fix login"}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:00:05Z","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/repo/src/auth.rs"}},{"type":"tool_use","id":"a1","name":"Task","input":{"subagent_type":"Explore","prompt":"find callers"}}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:01:05Z","message":{"content":[{"type":"tool_result","tool_use_id":"t1"},{"type":"tool_result","tool_use_id":"a1"}]}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:01:10Z","message":{"content":[{"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"/repo/src/auth.rs"}}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:01:12Z","message":{"content":[{"type":"tool_result","tool_use_id":"t2","is_error":true,"content":"String to replace not found"}]}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:01:20Z","message":{"stop_reason":"end_turn","content":[{"type":"text","text":"Done"}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:05:00Z","message":{"content":"now the tests"}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:05:02Z","message":{"content":[{"type":"tool_use","id":"t3","name":"Write","input":{"file_path":"/repo/tests/auth.rs"}}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:05:03Z","message":{"content":[{"type":"tool_result","tool_use_id":"t3"}]}}"#, "\n",
+        );
+
+        let summary = summarize_reader(transcript.as_bytes(), &Config::default());
+        assert_eq!(summary.turns, 2);
+        assert_eq!(summary.duration_ms, Some(303_000));
+        assert_eq!(summary.tools["Edit"], ToolSummary { calls: 1, errors: 1 });
+        assert_eq!(summary.tools["Write"].calls, 1);
+        assert!(!summary.tools.contains_key("Task"));
+        assert_eq!(summary.agents[0].agent_type, "Explore");
+        assert_eq!(summary.agents[0].duration_ms, Some(60_000));
+        assert!(summary.files.read.contains("/repo/src/auth.rs"));
+        assert!(summary.files.modified.is_empty());
+        assert!(summary.files.created.contains("/repo/tests/auth.rs"));
+
+        let json: serde_json::Value = serde_json::from_str(&format_summary_json(&summary)).unwrap();
+        assert_eq!(json["tools"]["Edit"]["errors"], 1);
+        assert_eq!(json["usage"]["total"], 0);
+
+        let text = format_summary(&summary);
+        assert!(text.contains("(5m03s), 2 turns"), "{text}");
+        assert!(text.contains("Created  1 file: /repo/tests/auth.rs"), "{text}");
+        assert!(text.contains("Edit ×1 (1 failed)"), "{text}");
+        assert!(text.contains("Agents   Explore 1m00s"), "{text}");
+    }
+
+    #[test]
+    fn test_summary_finishes_agents_from_earlier_turns() {
+        // The background agent outlives two turn resets before its result
+        let transcript = concat!(
+            r#"{"type":"user","timestamp":"2025-01-15T10:00:00Z","message":{"content":"research this"}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:00:01Z","message":{"content":[{"type":"tool_use","id":"a1","name":"Task","input":{"subagent_type":"Explore","prompt":"dig"}}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:01:00Z","message":{"content":"meanwhile, fix the typo"}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:01:01Z","message":{"stop_reason":"end_turn","content":[{"type":"text","text":"Fixed."}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:02:00Z","message":{"content":"and the readme"}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:02:01Z","message":{"content":[{"type":"text","text":"On it."}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2025-01-15T10:03:01Z","message":{"content":[{"type":"tool_result","tool_use_id":"a1","content":"found it"}]}}"#, "\n",
+        );
+
+        let summary = summarize_reader(transcript.as_bytes(), &Config::default());
+        assert_eq!(summary.turns, 3);
+        assert_eq!(summary.agents.len(), 1);
+        assert_eq!(summary.agents[0].status, "completed");
+        assert_eq!(summary.agents[0].duration_ms, Some(180_000));
+    }
+}
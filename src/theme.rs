@@ -0,0 +1,508 @@
+use crate::config::Config;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::env;
+
+// ============================================================================
+// Colors
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Rgb(u8, u8, u8),
+    /// Index into the 256-color palette (0-15 are the standard terminal colors)
+    Indexed(u8),
+}
+
+impl Color {
+    /// Parse `#rrggbb` or a palette index (`0`-`255`).
+    pub fn parse(s: &str) -> Option<Color> {
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        s.parse().ok().map(Color::Indexed)
+    }
+
+    fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Indexed(n) if n < 16 => ANSI16[n as usize],
+            Color::Indexed(n) if n < 232 => {
+                let n = n - 16;
+                (CUBE[(n / 36) as usize], CUBE[(n / 6 % 6) as usize], CUBE[(n % 6) as usize])
+            }
+            Color::Indexed(n) => {
+                let v = 8 + (n - 232) * 10;
+                (v, v, v)
+            }
+        }
+    }
+
+    fn to_256(self) -> u8 {
+        let (r, g, b) = match self {
+            Color::Indexed(n) => return n,
+            Color::Rgb(r, g, b) => (r, g, b),
+        };
+
+        // Nearest of the 6x6x6 cube and the 24-step gray ramp
+        let cube_index = |v: u8| {
+            (0..6u8)
+                .min_by_key(|i| (CUBE[*i as usize] as i32 - v as i32).abs())
+                .unwrap_or(0)
+        };
+        let cube = 16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b);
+
+        let avg = (r as u32 + g as u32 + b as u32) / 3;
+        let gray = 232 + (avg.saturating_sub(3) / 10).min(23) as u8;
+
+        let target = (r, g, b);
+        if distance(target, Color::Indexed(gray).to_rgb()) < distance(target, Color::Indexed(cube).to_rgb()) {
+            gray
+        } else {
+            cube
+        }
+    }
+
+    /// Map to the 16 standard colors by hue rather than by RGB distance,
+    /// which would turn every pastel color into white or gray.
+    fn to_16(self) -> u8 {
+        if let Color::Indexed(n) = self {
+            if n < 16 {
+                return n;
+            }
+        }
+
+        let (r, g, b) = self.to_rgb();
+        let (r, g, b) = (r as f32, g as f32, b as f32);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let lightness = (max + min) / 2.0;
+
+        if chroma < 40.0 {
+            return match lightness as u32 {
+                0..=63 => 0,
+                64..=159 => 8,
+                160..=215 => 7,
+                _ => 15,
+            };
+        }
+
+        let hue = if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+
+        let base = match hue as u32 {
+            30..=89 => 3,   // yellow
+            90..=149 => 2,  // green
+            150..=209 => 6, // cyan
+            210..=269 => 4, // blue
+            270..=329 => 5, // magenta
+            _ => 1,         // red
+        };
+        if lightness > 150.0 {
+            base + 8
+        } else {
+            base
+        }
+    }
+
+    fn escape(self, mode: ColorMode) -> String {
+        match mode {
+            ColorMode::None => String::new(),
+            // Palette indices are never upgraded: the standard colors stay
+            // plain SGR codes so they follow the terminal's own palette
+            ColorMode::TrueColor | ColorMode::Ansi256 => match self {
+                Color::Indexed(n) if n < 16 => ansi16_escape(n),
+                Color::Rgb(r, g, b) if mode == ColorMode::TrueColor => {
+                    format!("\x1b[38;2;{};{};{}m", r, g, b)
+                }
+                _ => format!("\x1b[38;5;{}m", self.to_256()),
+            },
+            ColorMode::Ansi16 | ColorMode::Auto => ansi16_escape(self.to_16()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Index(u8),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Index(n) => Ok(Color::Indexed(n)),
+            Raw::Text(s) => Color::parse(&s).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "invalid color \"{}\", expected \"#rrggbb\" or a palette index 0-255",
+                    s
+                ))
+            }),
+        }
+    }
+}
+
+fn ansi16_escape(n: u8) -> String {
+    if n < 8 {
+        format!("\x1b[0;{}m", 30 + n)
+    } else {
+        format!("\x1b[0;{}m", 90 + n - 8)
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// xterm defaults for the 16 standard colors
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ColorMode {
+    /// Detect from `NO_COLOR`, `COLORTERM` and `TERM`
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    #[serde(rename = "none")]
+    None,
+}
+
+impl ColorMode {
+    /// `Auto` replaced by the detected mode.
+    fn resolve(self) -> ColorMode {
+        if self == ColorMode::Auto {
+            ColorMode::detect()
+        } else {
+            self
+        }
+    }
+
+    fn detect() -> ColorMode {
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorMode::None;
+        }
+
+        let colorterm = env::var("COLORTERM").unwrap_or_default().to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorMode::TrueColor;
+        }
+
+        let term = env::var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            ColorMode::None
+        } else if term.contains("256color") {
+            ColorMode::Ansi256
+        } else {
+            ColorMode::Ansi16
+        }
+    }
+}
+
+// ============================================================================
+// Palettes
+// ============================================================================
+
+/// Colors for each role in the statusline.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub complete: Color,
+    pub running: Color,
+    pub error: Color,
+    /// Tools running or finishing past `slow_after_secs`
+    pub slow: Color,
+    /// Section icons
+    pub accent: Color,
+    /// Separators, context bar, cost
+    pub separator: Color,
+    pub branch: Color,
+    pub directory: Color,
+    pub model: Color,
+}
+
+const fn rgb(hex: u32) -> Color {
+    Color::Rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+pub const DEFAULT_THEME: &str = "catppuccin-mocha";
+
+pub const BUILTIN_THEMES: &[(&str, Palette)] = &[
+    (
+        "catppuccin-latte",
+        Palette {
+            complete: rgb(0x40a02b),
+            running: rgb(0xdf8e1d),
+            error: rgb(0xd20f39),
+            slow: rgb(0xfe640b),
+            accent: rgb(0x7287fd),
+            separator: rgb(0x8c8fa1),
+            branch: rgb(0xdf8e1d),
+            directory: rgb(0x1e66f5),
+            model: rgb(0x209fb5),
+        },
+    ),
+    (
+        "catppuccin-frappe",
+        Palette {
+            complete: rgb(0xa6d189),
+            running: rgb(0xe5c890),
+            error: rgb(0xe78284),
+            slow: rgb(0xef9f76),
+            accent: rgb(0xbabbf1),
+            separator: rgb(0x838ba7),
+            branch: rgb(0xe5c890),
+            directory: rgb(0x8caaee),
+            model: rgb(0x85c1dc),
+        },
+    ),
+    (
+        "catppuccin-macchiato",
+        Palette {
+            complete: rgb(0xa6da95),
+            running: rgb(0xeed49f),
+            error: rgb(0xed8796),
+            slow: rgb(0xf5a97f),
+            accent: rgb(0xb7bdf8),
+            separator: rgb(0x8087a2),
+            branch: rgb(0xeed49f),
+            directory: rgb(0x8aadf4),
+            model: rgb(0x7dc4e4),
+        },
+    ),
+    (
+        // Line 1 and separators keep the standard terminal colors of the
+        // original statusline.sh
+        "catppuccin-mocha",
+        Palette {
+            complete: rgb(0xa6e3a1),
+            running: rgb(0xf9e2af),
+            error: rgb(0xf38ba8),
+            slow: rgb(0xfab387),
+            accent: rgb(0xb4befe),
+            separator: Color::Indexed(7),
+            branch: Color::Indexed(3),
+            directory: Color::Indexed(4),
+            model: Color::Indexed(6),
+        },
+    ),
+    (
+        "solarized-dark",
+        Palette {
+            complete: rgb(0x859900),
+            running: rgb(0xb58900),
+            error: rgb(0xdc322f),
+            slow: rgb(0xcb4b16),
+            accent: rgb(0x6c71c4),
+            separator: rgb(0x586e75),
+            branch: rgb(0xb58900),
+            directory: rgb(0x268bd2),
+            model: rgb(0x2aa198),
+        },
+    ),
+    (
+        "solarized-light",
+        Palette {
+            complete: rgb(0x859900),
+            running: rgb(0xb58900),
+            error: rgb(0xdc322f),
+            slow: rgb(0xcb4b16),
+            accent: rgb(0x6c71c4),
+            separator: rgb(0x93a1a1),
+            branch: rgb(0xb58900),
+            directory: rgb(0x268bd2),
+            model: rgb(0x2aa198),
+        },
+    ),
+    (
+        "gruvbox-dark",
+        Palette {
+            complete: rgb(0xb8bb26),
+            running: rgb(0xfabd2f),
+            error: rgb(0xfb4934),
+            slow: rgb(0xfe8019),
+            accent: rgb(0xd3869b),
+            separator: rgb(0x928374),
+            branch: rgb(0xfabd2f),
+            directory: rgb(0x83a598),
+            model: rgb(0x8ec07c),
+        },
+    ),
+    (
+        "gruvbox-light",
+        Palette {
+            complete: rgb(0x79740e),
+            running: rgb(0xb57614),
+            error: rgb(0x9d0006),
+            slow: rgb(0xaf3a03),
+            accent: rgb(0x8f3f71),
+            separator: rgb(0x928374),
+            branch: rgb(0xb57614),
+            directory: rgb(0x076678),
+            model: rgb(0x427b58),
+        },
+    ),
+    (
+        "nord",
+        Palette {
+            complete: rgb(0xa3be8c),
+            running: rgb(0xebcb8b),
+            error: rgb(0xbf616a),
+            slow: rgb(0xd08770),
+            accent: rgb(0xb48ead),
+            separator: rgb(0x616e88),
+            branch: rgb(0xebcb8b),
+            directory: rgb(0x81a1c1),
+            model: rgb(0x88c0d0),
+        },
+    ),
+];
+
+pub fn builtin(name: &str) -> Option<Palette> {
+    BUILTIN_THEMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, p)| *p)
+}
+
+// ============================================================================
+// Resolved Theme
+// ============================================================================
+
+/// Palette rendered to escape sequences for the active color mode.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub complete: String,
+    pub running: String,
+    pub error: String,
+    pub slow: String,
+    pub accent: String,
+    pub separator: String,
+    pub branch: String,
+    pub directory: String,
+    pub model: String,
+    /// Per-MCP-server colors from `[mcp.servers.*]`
+    pub mcp_servers: HashMap<String, String>,
+    pub reset: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(&builtin(DEFAULT_THEME).unwrap(), ColorMode::TrueColor)
+    }
+}
+
+impl Theme {
+    pub fn new(palette: &Palette, mode: ColorMode) -> Self {
+        let mode = mode.resolve();
+        let reset = if mode == ColorMode::None { "" } else { "\x1b[0m" };
+
+        Theme {
+            complete: palette.complete.escape(mode),
+            running: palette.running.escape(mode),
+            error: palette.error.escape(mode),
+            slow: palette.slow.escape(mode),
+            accent: palette.accent.escape(mode),
+            separator: palette.separator.escape(mode),
+            branch: palette.branch.escape(mode),
+            directory: palette.directory.escape(mode),
+            model: palette.model.escape(mode),
+            mcp_servers: HashMap::new(),
+            reset: reset.to_string(),
+        }
+    }
+
+    /// Resolve the configured theme. The config is validated on load, so an
+    /// unknown name here can only mean defaults.
+    pub fn from_config(config: &Config) -> Self {
+        Theme::with_mode(config, config.theme.colors.resolve())
+    }
+
+    /// Like [`Theme::from_config`], for [`crate::tmux::from_ansi`]. `auto`
+    /// means truecolor here: tmux adapts colors to the client terminal
+    /// itself, and the status command's `TERM` says nothing about it.
+    pub fn for_tmux(config: &Config) -> Self {
+        let mode = match config.theme.colors {
+            // NO_COLOR still applies
+            ColorMode::Auto if ColorMode::detect() == ColorMode::None => ColorMode::None,
+            ColorMode::Auto => ColorMode::TrueColor,
+            mode => mode,
+        };
+        Theme::with_mode(config, mode)
+    }
+
+    fn with_mode(config: &Config, mode: ColorMode) -> Self {
+        let palette = config
+            .palette(&config.theme.name)
+            .unwrap_or_else(|| builtin(DEFAULT_THEME).unwrap());
+
+        let mut theme = Theme::new(&palette, mode);
+        theme.mcp_servers = config
+            .mcp
+            .servers
+            .iter()
+            .filter_map(|(name, server)| Some((name.clone(), server.color?.escape(mode))))
+            .collect();
+        theme
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_downgrades_color_depth() {
+        let mocha = builtin("catppuccin-mocha").unwrap();
+
+        let truecolor = Theme::new(&mocha, ColorMode::TrueColor);
+        assert_eq!(truecolor.complete, "\x1b[38;2;166;227;161m");
+        assert_eq!(truecolor.separator, "\x1b[0;37m");
+
+        let ansi256 = Theme::new(&mocha, ColorMode::Ansi256);
+        assert_eq!(ansi256.complete, "\x1b[38;5;151m");
+
+        let ansi16 = Theme::new(&mocha, ColorMode::Ansi16);
+        assert_eq!(ansi16.complete, "\x1b[0;92m");
+        assert_eq!(ansi16.error, "\x1b[0;91m");
+
+        let plain = Theme::new(&mocha, ColorMode::None);
+        assert_eq!(plain.complete, "");
+        assert_eq!(plain.reset, "");
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ============================================================================
+// Timestamps & Durations
+// ============================================================================
+
+/// Current wall-clock time in milliseconds since the Unix epoch.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Milliseconds from `start` to `end`, using `now_ms` for a missing or
+/// unparseable end (still running) or start.
+pub fn calculate_elapsed(start: &Option<String>, end: &Option<String>, now_ms: u64) -> u64 {
+    let start_ms = start
+        .as_ref()
+        .and_then(|s| parse_timestamp(s))
+        .unwrap_or(now_ms);
+
+    let end_ms = end
+        .as_ref()
+        .and_then(|s| parse_timestamp(s))
+        .unwrap_or(now_ms);
+
+    end_ms.saturating_sub(start_ms)
+}
+
+/// Human-readable duration: `850ms`, `12s`, `1m12s`, `2h03m`.
+pub fn format_duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs == 0 {
+        format!("{}ms", ms)
+    } else if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+/// Parse an RFC 3339 timestamp (`2025-01-15T10:30:00.123+02:00`) into
+/// milliseconds since the Unix epoch.
+pub fn parse_timestamp(ts: &str) -> Option<u64> {
+    let ts = ts.trim();
+    if ts.len() < 20 || !ts.is_ascii() {
+        return None;
+    }
+    let bytes = ts.as_bytes();
+
+    let num = |range: std::ops::Range<usize>| -> Option<u32> {
+        let s = ts.get(range)?;
+        if !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    };
+
+    if bytes[4] != b'-' || bytes[7] != b'-' || bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    // Allow a leap second (60); it folds into the next minute
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Fractional seconds: keep millisecond precision
+    let mut rest = &ts[19..];
+    let mut millis = 0u64;
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        for (i, b) in frac.bytes().take(3).take_while(|b| b.is_ascii_digit()).enumerate() {
+            millis += (b - b'0') as u64 * 10u64.pow(2 - i as u32);
+        }
+        rest = &frac[digits..];
+    }
+
+    let offset_secs: i64 = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes().first() {
+                Some(b'+') => 1,
+                Some(b'-') => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return None;
+            }
+            let hours: i64 = rest[1..3].parse().ok()?;
+            let minutes: i64 = rest[4..6].parse().ok()?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    let days = days_from_civil(year as i64, month, day);
+    let secs = days * 86400 + (hour * 3600 + minute * 60 + second) as i64 - offset_secs;
+    if secs < 0 {
+        return None;
+    }
+
+    Some(secs as u64 * 1000 + millis)
+}
+
+fn is_leap_year(year: u32) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+/// (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp_rfc3339() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2024-02-29T12:00:00.250Z"), Some(1_709_208_000_250));
+        assert_eq!(parse_timestamp("2024-02-29T14:00:00.250+02:00"), Some(1_709_208_000_250));
+        assert_eq!(parse_timestamp("2023-02-29T12:00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-13-01T12:00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-01-01T12:00:00"), None);
+
+        // Across a month boundary
+        let start = Some("2025-01-31T23:59:59.900Z".to_string());
+        let end = Some("2025-02-01T00:01:12.000Z".to_string());
+        assert_eq!(calculate_elapsed(&start, &end, 0), 72_100);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(850), "850ms");
+        assert_eq!(format_duration(12_400), "12s");
+        assert_eq!(format_duration(72_000), "1m12s");
+        assert_eq!(format_duration(2 * 3_600_000 + 3 * 60_000), "2h03m");
+    }
+}
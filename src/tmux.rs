@@ -0,0 +1,81 @@
+// ============================================================================
+// Tmux Output (`--format tmux`)
+// ============================================================================
+
+/// Rewrite a rendered line for tmux's `status-left`/`status-right`: color
+/// escapes become `#[fg=...]` directives and a literal `#` is doubled so
+/// tmux doesn't expand it as a format.
+///
+/// Lines are rendered and fitted to the width as usual first, so only the
+/// foreground colors and resets produced by [`crate::theme::Theme`] need
+/// translating; any other escape sequence is dropped.
+pub fn from_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                if chars.clone().next() != Some('[') {
+                    continue;
+                }
+                chars.next();
+                let mut params = String::new();
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        if c == 'm' {
+                            out.push_str(&directive(&params));
+                        }
+                        break;
+                    }
+                    params.push(c);
+                }
+            }
+            '#' => out.push_str("##"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// The tmux directive for the SGR parameters `params`, e.g. `38;2;166;227;161`.
+fn directive(params: &str) -> String {
+    let codes: Vec<u8> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut fg = "default".to_string();
+
+    let mut codes = codes.iter();
+    while let Some(&code) = codes.next() {
+        fg = match code {
+            30..=37 => format!("colour{}", code - 30),
+            90..=97 => format!("colour{}", code - 90 + 8),
+            38 => match (codes.next(), codes.next()) {
+                (Some(5), Some(n)) => format!("colour{}", n),
+                (Some(2), Some(r)) => match (codes.next(), codes.next()) {
+                    (Some(g), Some(b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+                    _ => continue,
+                },
+                _ => continue,
+            },
+            39 | 0 => "default".to_string(),
+            _ => continue,
+        };
+    }
+
+    format!("#[fg={}]", fg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ansi_colors_become_tmux_directives() {
+        assert_eq!(
+            from_ansi("\x1b[38;2;166;227;161m✓\x1b[0m Read ×2 \x1b[0;37m|\x1b[0m \x1b[38;5;151mBash\x1b[0m"),
+            "#[fg=#a6e3a1]✓#[fg=default] Read ×2 #[fg=colour7]|#[fg=default] #[fg=colour151]Bash#[fg=default]"
+        );
+        assert_eq!(from_ansi("\x1b[0;91m✗ grep #TODO\x1b[0m"), "#[fg=colour9]✗ grep ##TODO#[fg=default]");
+        assert_eq!(from_ansi("plain"), "plain");
+    }
+}
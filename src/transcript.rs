@@ -0,0 +1,2058 @@
+use crate::checkpoint;
+use crate::config::Config;
+use crate::session::{SessionState, SessionStatus};
+use crate::time::parse_timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// Lifecycle of a tool, agent, or skill invocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Status {
+    Running,
+    Completed,
+    Error,
+}
+
+/// A regular tool call still waiting for its `tool_result`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningTool {
+    pub name: String,
+    /// Short description of the input (file path, pattern, command)
+    pub target: Option<String>,
+    /// RFC 3339 timestamp of the tool_use line
+    pub start_time: Option<String>,
+}
+
+/// Time spent in completed calls of one tool.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolDuration {
+    pub total_ms: u64,
+    /// Longest single call
+    pub max_ms: u64,
+}
+
+/// The most recent failed tool call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolError {
+    pub tool: String,
+    /// First line of the error result, truncated
+    pub message: String,
+    pub timestamp: Option<String>,
+}
+
+/// Regular tool activity in the current turn.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ToolState {
+    pub running: Vec<RunningTool>,
+    /// Successful completions per tool name
+    pub completed: HashMap<String, u32>,
+    /// Failed calls (`is_error` results) per tool name
+    pub errors: HashMap<String, u32>,
+    pub last_error: Option<ToolError>,
+    /// Time spent per tool name, for calls with both timestamps
+    pub durations: HashMap<String, ToolDuration>,
+    /// Finished calls of the whole session, oldest first, up to
+    /// [`TOOL_LOG_LIMIT`]; unlike the counts above, not reset per turn
+    pub log: Vec<ToolCall>,
+}
+
+/// Finished calls kept in [`ToolState::log`].
+pub const TOOL_LOG_LIMIT: usize = 200;
+
+/// A finished regular tool call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    pub target: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub is_error: bool,
+}
+
+/// Agents kept in [`TranscriptState::agent_log`].
+pub const AGENT_LOG_LIMIT: usize = 100;
+
+/// One Task call, kept past its turn unlike [`AgentEntry`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentRun {
+    /// Task tool_use id
+    pub id: String,
+    pub agent_type: String,
+    pub status: Status,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
+/// A subagent spawned with the `Task` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentEntry {
+    /// `subagent_type` from the Task input, or `"agent"`
+    pub agent_type: String,
+    pub status: Status,
+    /// RFC 3339 timestamp of the tool_use line
+    pub start_time: Option<String>,
+    /// RFC 3339 timestamp of the tool_result line
+    pub end_time: Option<String>,
+    pub start_turn: u32, // Track which turn the agent was started in
+    /// `agentId` of the agent's sidechain lines, once linked
+    pub agent_id: Option<String>,
+    pub activity: AgentActivity,
+}
+
+/// What a subagent did in its own sidechain.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AgentActivity {
+    /// Tools still running, oldest first; the last one is the current action
+    pub running: Vec<RunningTool>,
+    /// Completion count per tool name
+    pub completed: HashMap<String, u32>,
+    pub todos: TodoState,
+    pub usage: TokenUsage,
+}
+
+impl AgentActivity {
+    pub fn current(&self) -> Option<&RunningTool> {
+        self.running.last()
+    }
+}
+
+/// A `Skill` invocation, deduplicated by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillEntry {
+    pub name: String,
+    pub status: Status,
+}
+
+/// Progress of the latest `TodoWrite` list.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TodoState {
+    /// `activeForm` of the in-progress todo
+    pub current: Option<String>,
+    pub done: u32,
+    pub total: u32,
+    /// The whole list, in order
+    pub items: Vec<Todo>,
+}
+
+/// One entry of a `TodoWrite` list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Todo {
+    pub content: String,
+    pub active_form: Option<String>,
+    /// `pending`, `in_progress`, or `completed`
+    pub status: String,
+}
+
+/// Lifecycle of a background shell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Running,
+    /// Exited with status 0
+    Completed,
+    /// Exited with a non-zero status
+    Failed,
+    /// Stopped with `KillShell`
+    Killed,
+}
+
+/// A shell started with `Bash` and `run_in_background: true`.
+///
+/// Unlike tools, jobs outlive the turn that started them: finished jobs are
+/// dropped at the next turn, running ones stay until killed or seen exiting
+/// in a `BashOutput` result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundJob {
+    /// Shell id from the tool result (e.g. `bash_1`), or the tool_use id
+    /// when the result didn't name one
+    pub id: String,
+    pub command: String,
+    pub status: JobStatus,
+    pub exit_code: Option<i32>,
+    /// RFC 3339 timestamp of the Bash tool_use line
+    pub start_time: Option<String>,
+    /// Timestamp of the result that reported the exit or kill
+    pub end_time: Option<String>,
+}
+
+/// A pending tool call that affects background jobs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum JobCall {
+    /// Background `Bash`; the shell id arrives with the result
+    Start { command: String, start_time: Option<String> },
+    /// `BashOutput` polling a shell
+    Poll { shell_id: String },
+    /// `KillShell` stopping a shell
+    Kill { shell_id: String },
+}
+
+/// A pending Read, Write, Edit, MultiEdit, or NotebookEdit call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileCall {
+    tool: String,
+    path: String,
+    /// Lines changed by an Edit or MultiEdit, known from the input alone
+    diff: DiffStats,
+    /// New content of a Write, diffed against the original from its result
+    content: Option<String>,
+}
+
+impl FileCall {
+    fn new(tool: &str, path: &str, input: Option<&Value>) -> Self {
+        let text = |value: &Value, key: &str| value.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let mut call = FileCall {
+            tool: tool.to_string(),
+            path: path.to_string(),
+            diff: DiffStats::default(),
+            content: None,
+        };
+        let Some(input) = input else {
+            return call;
+        };
+        match tool {
+            "Edit" => call.diff = DiffStats::between(&text(input, "old_string"), &text(input, "new_string")),
+            "MultiEdit" => {
+                for edit in input.get("edits").and_then(|v| v.as_array()).into_iter().flatten() {
+                    call.diff.add(DiffStats::between(&text(edit, "old_string"), &text(edit, "new_string")));
+                }
+            }
+            "Write" => call.content = Some(text(input, "content")),
+            _ => {}
+        }
+        call
+    }
+
+    /// Lines changed once the call succeeded. A Write without `originalFile`
+    /// in its result counts every line as added.
+    fn diff(&self, details: Option<&Value>) -> DiffStats {
+        match &self.content {
+            Some(content) => {
+                let original = details.and_then(|d| d.get("originalFile")).and_then(|v| v.as_str());
+                DiffStats::between(original.unwrap_or(""), content)
+            }
+            None => self.diff,
+        }
+    }
+}
+
+/// Lines added and removed by file edits.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub added: u64,
+    pub removed: u64,
+}
+
+impl DiffStats {
+    /// Lines that differ between `old` and `new` once the common leading and
+    /// trailing lines are dropped. Cheaper than a real diff, and exact for
+    /// the single contiguous change an Edit usually is.
+    pub fn between(old: &str, new: &str) -> Self {
+        let old: Vec<&str> = old.lines().collect();
+        let new: Vec<&str> = new.lines().collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        DiffStats {
+            added: (new.len() - prefix - suffix) as u64,
+            removed: (old.len() - prefix - suffix) as u64,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+
+    fn add(&mut self, other: DiffStats) {
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+}
+
+/// Lines changed by successful Edit, MultiEdit, and Write calls in the main
+/// conversation.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffState {
+    pub turn: DiffStats,
+    pub session: DiffStats,
+    /// Session totals per absolute path
+    pub by_file: BTreeMap<String, DiffStats>,
+}
+
+impl DiffState {
+    fn record(&mut self, path: &str, diff: DiffStats) {
+        if diff.is_empty() {
+            return;
+        }
+        self.turn.add(diff);
+        self.session.add(diff);
+        self.by_file.entry(path.to_string()).or_default().add(diff);
+    }
+}
+
+/// Token counts from an assistant message's `usage`, or a sum of them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl TokenUsage {
+    fn from_value(usage: &Value) -> Self {
+        let get = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        TokenUsage {
+            input_tokens: get("input_tokens"),
+            output_tokens: get("output_tokens"),
+            cache_creation_input_tokens: get("cache_creation_input_tokens"),
+            cache_read_input_tokens: get("cache_read_input_tokens"),
+        }
+    }
+
+    /// All tokens processed, cached or not.
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+
+    /// Share of input tokens served from the prompt cache, or `None` before
+    /// any input was sent.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let input = self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens;
+        (input > 0).then(|| self.cache_read_input_tokens as f64 / input as f64)
+    }
+
+    fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+
+    /// Per-field growth from `earlier` to `self`.
+    fn since(&self, earlier: &TokenUsage) -> TokenUsage {
+        TokenUsage {
+            input_tokens: self.input_tokens.saturating_sub(earlier.input_tokens),
+            output_tokens: self.output_tokens.saturating_sub(earlier.output_tokens),
+            cache_creation_input_tokens: self
+                .cache_creation_input_tokens
+                .saturating_sub(earlier.cache_creation_input_tokens),
+            cache_read_input_tokens: self
+                .cache_read_input_tokens
+                .saturating_sub(earlier.cache_read_input_tokens),
+        }
+    }
+
+    fn max(&self, other: &TokenUsage) -> TokenUsage {
+        TokenUsage {
+            input_tokens: self.input_tokens.max(other.input_tokens),
+            output_tokens: self.output_tokens.max(other.output_tokens),
+            cache_creation_input_tokens: self.cache_creation_input_tokens.max(other.cache_creation_input_tokens),
+            cache_read_input_tokens: self.cache_read_input_tokens.max(other.cache_read_input_tokens),
+        }
+    }
+}
+
+/// Token usage of one model over the current turn and the whole session.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub turn: TokenUsage,
+    pub session: TokenUsage,
+}
+
+/// Token usage summed over the current turn and the whole session.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct UsageState {
+    pub turn: TokenUsage,
+    pub session: TokenUsage,
+    /// Same, split by `message.model`, for pricing
+    pub by_model: HashMap<String, ModelUsage>,
+}
+
+/// Context compactions (`/compact` or automatic) seen in the session.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionState {
+    pub count: u32,
+    /// Timestamp of the latest `compact_boundary` line
+    pub last_time: Option<String>,
+    /// `manual` or `auto`
+    pub last_trigger: Option<String>,
+    /// Context size just before the latest compaction
+    pub last_pre_tokens: Option<u64>,
+}
+
+/// Files touched by successful file tool calls, as absolute paths. A file
+/// can be in more than one set, e.g. created and then modified.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSet {
+    pub read: BTreeSet<String>,
+    pub created: BTreeSet<String>,
+    pub modified: BTreeSet<String>,
+}
+
+impl FileSet {
+    /// Distinct files across all three sets.
+    pub fn len(&self) -> usize {
+        self.read.union(&self.created).chain(self.modified.iter()).collect::<BTreeSet<_>>().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read.is_empty() && self.created.is_empty() && self.modified.is_empty()
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        self.read.contains(path) || self.created.contains(path) || self.modified.contains(path)
+    }
+}
+
+/// Files touched by the main conversation (not subagents).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileState {
+    pub turn: FileSet,
+    pub session: FileSet,
+    /// Most recently touched file
+    pub last: Option<String>,
+    /// `cwd` of the first line that had one, for relative paths
+    pub workspace: Option<String>,
+}
+
+impl FileState {
+    fn record(&mut self, call: FileCall, details: Option<&Value>) {
+        let FileCall { tool, path, .. } = call;
+        let kind = match tool.as_str() {
+            "Read" => FileKind::Read,
+            // Claude Code reports `create` or `update`; without that, a file
+            // never seen before is assumed new, since it has to be read
+            // before it can be overwritten
+            "Write" => match details.and_then(|d| d.get("type")).and_then(|v| v.as_str()) {
+                Some("create") => FileKind::Created,
+                Some(_) => FileKind::Modified,
+                None if self.session.contains(&path) => FileKind::Modified,
+                None => FileKind::Created,
+            },
+            _ => FileKind::Modified,
+        };
+        for set in [&mut self.turn, &mut self.session] {
+            let files = match kind {
+                FileKind::Read => &mut set.read,
+                FileKind::Created => &mut set.created,
+                FileKind::Modified => &mut set.modified,
+            };
+            files.insert(path.clone());
+        }
+        self.last = Some(path);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FileKind {
+    Read,
+    Created,
+    Modified,
+}
+
+/// `path` relative to `workspace` when inside it, else unchanged.
+pub fn relative_path<'a>(path: &'a str, workspace: Option<&str>) -> &'a str {
+    let Some(workspace) = workspace.map(|w| w.trim_end_matches('/')).filter(|w| !w.is_empty()) else {
+        return path;
+    };
+    path.strip_prefix(workspace)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .filter(|rest| !rest.is_empty())
+        .unwrap_or(path)
+}
+
+/// Everything the statusline shows, as of the end of the transcript.
+///
+/// Tools, agents, and skills cover the current turn; todos persist.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TranscriptState {
+    pub tools: ToolState,
+    pub agents: Vec<AgentEntry>,
+    /// Agents of the whole session, oldest first, up to [`AGENT_LOG_LIMIT`]
+    pub agent_log: Vec<AgentRun>,
+    pub skills: Vec<SkillEntry>,
+    pub todos: TodoState,
+    pub usage: UsageState,
+    /// Background shells, oldest first
+    pub jobs: Vec<BackgroundJob>,
+    pub session: SessionStatus,
+    pub compaction: CompactionState,
+    pub files: FileState,
+    pub diff: DiffState,
+}
+
+// ============================================================================
+// JSONL Parsing
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct TodoItem {
+    content: Option<String>,
+    status: Option<String>,
+    #[serde(rename = "activeForm")]
+    active_form: Option<String>,
+}
+
+/// Truncate a file path for display, matching claude-hud's logic.
+/// If path > max_len, show `.../<filename>`.
+pub(crate) fn truncate_path(path: &str, max_len: usize) -> String {
+    // Normalize Windows backslashes to forward slashes
+    let normalized = path.replace('\\', "/");
+
+    if normalized.len() <= max_len {
+        return normalized;
+    }
+
+    // Extract filename
+    let filename = normalized.rsplit('/').next().unwrap_or(&normalized);
+
+    // If filename itself is too long, truncate it
+    if filename.len() >= max_len {
+        return format!("{}...", &filename[..max_len.saturating_sub(3)]);
+    }
+
+    format!(".../{}", filename)
+}
+
+/// Split an MCP tool name, `mcp__<server>__<tool>`, into server and tool.
+pub fn parse_mcp_name(name: &str) -> Option<(&str, &str)> {
+    let (server, tool) = name.strip_prefix("mcp__")?.split_once("__")?;
+    (!server.is_empty() && !tool.is_empty()).then_some((server, tool))
+}
+
+/// Argument keys that usually say what an MCP call is about, most telling
+/// first.
+const MCP_TARGET_KEYS: &[&str] = &[
+    "query", "url", "path", "file_path", "title", "name", "repo", "issue_number", "pull_number", "id",
+];
+
+/// Full path of the file a Read, Write, Edit, MultiEdit, or NotebookEdit
+/// call works on.
+pub fn file_path<'a>(name: &str, input: Option<&'a Value>) -> Option<&'a str> {
+    if !matches!(name, "Read" | "Write" | "Edit" | "MultiEdit" | "NotebookEdit") {
+        return None;
+    }
+    let input = input?;
+    input
+        .get("file_path")
+        .or_else(|| input.get("notebook_path"))
+        .and_then(|v| v.as_str())
+}
+
+/// Short, display-ready description of a tool call's input, if the tool is
+/// one we know how to describe.
+pub fn extract_target(name: &str, input: Option<&Value>) -> Option<String> {
+    let input = input?;
+
+    if parse_mcp_name(name).is_some() {
+        return MCP_TARGET_KEYS.iter().find_map(|key| match input.get(*key)? {
+            Value::String(s) if !s.is_empty() => Some(truncate(s, 25)),
+            Value::Number(n) => Some(format!("#{}", n)),
+            _ => None,
+        });
+    }
+
+    match name {
+        "Read" | "Write" | "Edit" | "MultiEdit" | "NotebookEdit" => {
+            file_path(name, Some(input)).map(|path| truncate_path(path, 30))
+        }
+        "Glob" => input
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .map(|s| truncate(s, 20)),
+        "Grep" => input
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .map(|s| truncate(s, 20)),
+        "Bash" => input
+            .get("command")
+            .and_then(|v| v.as_str())
+            .map(|s| truncate(s, 25)),
+        "Task" => input
+            .get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| truncate(s, 30)),
+        "WebFetch" | "WebSearch" => input
+            .get("url")
+            .or_else(|| input.get("query"))
+            .and_then(|v| v.as_str())
+            .map(|s| truncate(s, 25)),
+        _ => None,
+    }
+}
+
+pub(crate) fn truncate(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        // Back off to a char boundary so multi-byte text can't panic
+        let mut end = max_len.saturating_sub(3);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &s[..end])
+    }
+}
+
+/// Classify a tool call that starts, polls, or kills a background shell.
+fn job_call(name: &str, input: Option<&Value>, timestamp: &Option<String>) -> Option<JobCall> {
+    let input = input?;
+    let shell_id = || {
+        input
+            .get("bash_id")
+            .or_else(|| input.get("shell_id"))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+
+    match name {
+        "Bash" if input.get("run_in_background").and_then(|v| v.as_bool()) == Some(true) => {
+            Some(JobCall::Start {
+                command: input.get("command").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                start_time: timestamp.clone(),
+            })
+        }
+        "BashOutput" => Some(JobCall::Poll { shell_id: shell_id()? }),
+        // KillBash is the tool's name in older Claude Code releases
+        "KillShell" | "KillBash" => Some(JobCall::Kill { shell_id: shell_id()? }),
+        _ => None,
+    }
+}
+
+/// Text of a tool_result block, whether `content` is a string or a list of
+/// text blocks.
+fn result_text(block: &Value) -> String {
+    match block.get("content") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// First non-empty line of an error result, without the
+/// `<tool_use_error>` wrapper Claude Code puts around some of them.
+fn error_message(text: &str) -> String {
+    let text = tag_value(text, "tool_use_error").unwrap_or(text);
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+    truncate(line, 120)
+}
+
+/// Contents of the first `<tag>...</tag>` in `text`.
+fn tag_value<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = text.find(&open)? + open.len();
+    let end = start + text[start..].find(&close)?;
+    Some(text[start..end].trim())
+}
+
+/// A sidechain line and what the main pass already extracted from it.
+struct AgentLine<'a> {
+    value: &'a Value,
+    agent_id: &'a str,
+    line_type: &'a str,
+    /// Usage already added to the session totals
+    usage: TokenUsage,
+}
+
+/// The tool_result answering a [`JobCall`].
+struct JobResult<'a> {
+    tool_use_id: &'a str,
+    text: &'a str,
+    /// The line's `toolUseResult`, structured details some versions add
+    details: Option<&'a Value>,
+    is_error: bool,
+    timestamp: &'a Option<String>,
+}
+
+fn apply_job_result(call: JobCall, result: &JobResult, jobs: &mut Vec<BackgroundJob>, on_event: &mut impl FnMut(Event)) {
+    if result.is_error {
+        return;
+    }
+
+    match call {
+        JobCall::Start { command, start_time } => {
+            let id = result
+                .details
+                .and_then(|d| d.get("backgroundTaskId").or_else(|| d.get("shellId")))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .or_else(|| {
+                    // "Command running in background with ID: bash_1"
+                    let rest = &result.text[result.text.find("ID:")? + 3..];
+                    let id: String = rest
+                        .trim_start()
+                        .chars()
+                        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                        .collect();
+                    (!id.is_empty()).then_some(id)
+                })
+                .unwrap_or_else(|| result.tool_use_id.to_string());
+
+            jobs.retain(|job| job.id != id);
+            on_event(Event::JobStarted {
+                id: id.clone(),
+                command: command.clone(),
+                timestamp: start_time.clone(),
+            });
+            jobs.push(BackgroundJob {
+                id,
+                command,
+                status: JobStatus::Running,
+                exit_code: None,
+                start_time,
+                end_time: None,
+            });
+        }
+        JobCall::Poll { shell_id } => {
+            let Some(job) = jobs.iter_mut().find(|j| j.id == shell_id && j.status == JobStatus::Running) else {
+                return;
+            };
+            let exit_code = tag_value(result.text, "exit_code").and_then(|c| c.parse().ok());
+            let status = match tag_value(result.text, "status") {
+                Some("completed") if exit_code.unwrap_or(0) != 0 => JobStatus::Failed,
+                Some("completed") => JobStatus::Completed,
+                Some("failed") => JobStatus::Failed,
+                Some("killed") => JobStatus::Killed,
+                _ => return,
+            };
+            job.status = status;
+            job.exit_code = exit_code;
+            job.end_time = result.timestamp.clone();
+            on_event(Event::JobFinished { id: shell_id, status, exit_code });
+        }
+        JobCall::Kill { shell_id } => {
+            let Some(job) = jobs.iter_mut().find(|j| j.id == shell_id && j.status == JobStatus::Running) else {
+                return;
+            };
+            job.status = JobStatus::Killed;
+            job.end_time = result.timestamp.clone();
+            on_event(Event::JobFinished {
+                id: shell_id,
+                status: JobStatus::Killed,
+                exit_code: None,
+            });
+        }
+    }
+}
+
+/// Add an assistant line's `message.usage` to the totals, counting only what
+/// grew since an earlier line of the same message. Returns the added usage.
+fn record_usage(
+    value: &Value,
+    totals: &mut UsageState,
+    seen: &mut HashMap<String, TokenUsage>,
+    on_event: &mut impl FnMut(Event),
+) -> TokenUsage {
+    let Some(message) = value.get("message") else {
+        return TokenUsage::default();
+    };
+    let Some(usage) = message.get("usage") else {
+        return TokenUsage::default();
+    };
+    let usage = TokenUsage::from_value(usage);
+
+    let delta = match message.get("id").and_then(|v| v.as_str()) {
+        Some(id) => {
+            let previous = seen.entry(id.to_string()).or_default();
+            let delta = usage.since(previous);
+            *previous = previous.max(&usage);
+            delta
+        }
+        None => usage,
+    };
+    if delta == TokenUsage::default() {
+        return delta;
+    }
+
+    totals.turn.add(&delta);
+    totals.session.add(&delta);
+
+    let model = message.get("model").and_then(|v| v.as_str()).unwrap_or("unknown");
+    let by_model = totals.by_model.entry(model.to_string()).or_default();
+    by_model.turn.add(&delta);
+    by_model.session.add(&delta);
+    on_event(Event::UsageUpdated {
+        turn: totals.turn,
+        session: totals.session,
+    });
+    delta
+}
+
+fn todos_event(todos: &TodoState) -> Event {
+    Event::TodosUpdated {
+        done: todos.done,
+        total: todos.total,
+        current: todos.current.clone(),
+    }
+}
+
+fn update_todos(state: &mut TodoState, todos: &[TodoItem]) {
+    state.total = todos.len() as u32;
+    state.done = todos
+        .iter()
+        .filter(|t| t.status.as_deref() == Some("completed"))
+        .count() as u32;
+    state.current = todos
+        .iter()
+        .find(|t| t.status.as_deref() == Some("in_progress"))
+        .and_then(|t| t.active_form.clone());
+    state.items = todos
+        .iter()
+        .map(|t| Todo {
+            content: t.content.clone().unwrap_or_default(),
+            active_form: t.active_form.clone(),
+            status: t.status.clone().unwrap_or_else(|| "pending".to_string()),
+        })
+        .collect();
+}
+
+// ============================================================================
+// Transcript Parsing
+// ============================================================================
+
+/// Event-sourcing parser over transcript lines.
+///
+/// Holds the in-flight maps between lines so parsing can be resumed from a
+/// checkpoint instead of replaying the whole file. Feed it lines with
+/// [`process_line`](Self::process_line) (or
+/// [`process_line_with`](Self::process_line_with) to observe [`Event`]s) and
+/// call [`finish`](Self::finish) or [`snapshot`](Self::snapshot) for the state.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TranscriptParser {
+    state: TranscriptState,
+    tool_starts: HashMap<String, RunningTool>,
+    agent_starts: HashMap<String, AgentEntry>,
+    skill_starts: HashMap<String, SkillEntry>,
+    job_calls: HashMap<String, JobCall>,
+    file_calls: HashMap<String, FileCall>,
+    // Sidechain `agentId` -> Task tool_use id
+    agent_ids: HashMap<String, String>,
+    // Task prompts of agents not linked to a sidechain yet, used to match
+    // the sidechain's first user message
+    agent_prompts: HashMap<String, String>,
+    // Running sidechain tools as (tool_use id, Task id, tool), oldest first
+    agent_tools: Vec<(String, String, RunningTool)>,
+    // Usage already counted per message id this turn. Claude Code writes one
+    // line per content block, each repeating the message's usage.
+    usage_seen: HashMap<String, TokenUsage>,
+    // Track if we've seen a user message (pending new turn)
+    pending_reset: bool,
+    // Track current turn number for agent aging
+    current_turn: u32,
+}
+
+/// A state change observed while processing a line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The assistant started answering a new user message; per-turn
+    /// activity was reset
+    TurnStarted { turn: u32 },
+    ToolStarted {
+        id: String,
+        name: String,
+        target: Option<String>,
+        /// Full path for file tools, see [`file_path`]
+        path: Option<String>,
+        timestamp: Option<String>,
+    },
+    ToolFinished {
+        id: String,
+        name: String,
+        is_error: bool,
+        timestamp: Option<String>,
+    },
+    AgentStarted {
+        id: String,
+        agent_type: String,
+        timestamp: Option<String>,
+    },
+    AgentFinished {
+        id: String,
+        agent_type: String,
+        status: Status,
+        timestamp: Option<String>,
+    },
+    /// A subagent started a tool in its sidechain; `agent` is the Task id
+    AgentToolStarted {
+        agent: String,
+        id: String,
+        name: String,
+        target: Option<String>,
+    },
+    AgentToolFinished {
+        agent: String,
+        id: String,
+        name: String,
+        is_error: bool,
+    },
+    SkillStarted { id: String, name: String },
+    SkillFinished { id: String, name: String, status: Status },
+    TodosUpdated { done: u32, total: u32, current: Option<String> },
+    JobStarted { id: String, command: String, timestamp: Option<String> },
+    JobFinished { id: String, status: JobStatus, exit_code: Option<i32> },
+    /// A `compact_boundary` line; `count` includes this one
+    Compacted { count: u32, trigger: Option<String> },
+    /// A top-level message moved the session to a new state
+    SessionStateChanged { state: SessionState },
+    /// An assistant message reported new token usage
+    UsageUpdated { turn: TokenUsage, session: TokenUsage },
+}
+
+impl TranscriptParser {
+    /// Process one JSONL line. Blank and malformed lines are ignored.
+    pub fn process_line(&mut self, line: &str) {
+        self.process_line_with(line, |_| {});
+    }
+
+    /// Process one JSONL line, reporting each state change to `on_event`.
+    pub fn process_line_with(&mut self, line: &str, mut on_event: impl FnMut(Event)) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let value: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        let state = &mut self.state;
+        let tool_starts = &mut self.tool_starts;
+        let agent_starts = &mut self.agent_starts;
+        let skill_starts = &mut self.skill_starts;
+        let job_calls = &mut self.job_calls;
+
+        let line_type = value.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let timestamp = value.get("timestamp").and_then(|v| v.as_str()).map(String::from);
+
+        // Check if this is an agent-level message (has agentId) vs top-level conversation
+        let is_top_level = value.get("agentId").is_none();
+
+        // The summary injected after a compaction reads like a user message
+        // but continues the current turn
+        let is_compact_summary = value.get("isCompactSummary").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if line_type == "system" && value.get("subtype").and_then(|v| v.as_str()) == Some("compact_boundary") {
+            let metadata = value.get("compactMetadata");
+            let compaction = &mut state.compaction;
+            compaction.count += 1;
+            compaction.last_time = timestamp;
+            compaction.last_trigger = metadata.and_then(|m| m.get("trigger")).and_then(|v| v.as_str()).map(String::from);
+            compaction.last_pre_tokens = metadata.and_then(|m| m.get("preTokens")).and_then(|v| v.as_u64());
+            // Agents still running were started before the boundary; give
+            // them a fresh turn so the `/compact` turn doesn't age them out
+            for agent in agent_starts.values_mut().filter(|a| a.status == Status::Running) {
+                agent.start_turn = self.current_turn;
+            }
+            on_event(Event::Compacted {
+                count: compaction.count,
+                trigger: compaction.last_trigger.clone(),
+            });
+            return;
+        }
+
+        if line_type == "user" && is_top_level {
+            // Check if this is actually a tool result message (not a real user message)
+            let is_tool_result = value
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_array())
+                .map(|arr| {
+                    arr.iter().any(|block| {
+                        block.get("type").and_then(|t| t.as_str()) == Some("tool_result")
+                    })
+                })
+                .unwrap_or(false);
+
+            // Check if this is a meta message (skill content injection, system message, etc.)
+            let is_meta = value.get("isMeta").and_then(|v| v.as_bool()).unwrap_or(false);
+
+            // Check if this is a skill content message (has sourceToolUseID)
+            let is_skill_content = value.get("sourceToolUseID").is_some();
+
+            // Check if this is an agent notification (background task completion)
+            let is_agent_notification = value
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_str())
+                .map(|s| s.starts_with("<agent-notification>"))
+                .unwrap_or(false);
+
+            if !is_tool_result && !is_meta && !is_skill_content && !is_agent_notification && !is_compact_summary {
+                self.pending_reset = true;
+            }
+        }
+
+        // Reset activity when assistant starts responding (new turn)
+        if line_type == "assistant" && is_top_level && self.pending_reset {
+            self.current_turn += 1;
+            let current_turn = self.current_turn;
+            tool_starts.clear();
+            // Keep only agents that are BOTH running AND from the current or previous turn
+            // This ensures agents don't persist indefinitely if their tool_result is missing
+            agent_starts.retain(|_, agent| {
+                agent.status == Status::Running && agent.start_turn >= current_turn.saturating_sub(1)
+            });
+            self.agent_ids.retain(|_, task| agent_starts.contains_key(task));
+            self.agent_prompts.retain(|task, _| agent_starts.contains_key(task));
+            self.agent_tools.retain(|(_, task, _)| agent_starts.contains_key(task));
+            skill_starts.clear();
+            job_calls.clear();
+            self.file_calls.clear();
+            state.files.turn = FileSet::default();
+            state.diff.turn = DiffStats::default();
+            // Background shells outlive the turn; finished ones were shown
+            // for the rest of theirs
+            state.jobs.retain(|job| job.status == JobStatus::Running);
+            state.tools.completed.clear();
+            state.tools.errors.clear();
+            state.tools.last_error = None;
+            state.tools.durations.clear();
+            state.tools.running.clear();
+            state.agents.clear();
+            state.skills.clear();
+            state.usage.turn = TokenUsage::default();
+            for model in state.usage.by_model.values_mut() {
+                model.turn = TokenUsage::default();
+            }
+            self.usage_seen.clear();
+            self.pending_reset = false;
+            on_event(Event::TurnStarted { turn: current_turn });
+        }
+
+        let usage = if line_type == "assistant" {
+            record_usage(&value, &mut state.usage, &mut self.usage_seen, &mut on_event)
+        } else {
+            TokenUsage::default()
+        };
+
+        // Sidechain lines describe a subagent's own work
+        if let Some(agent_id) = value.get("agentId").and_then(|v| v.as_str()) {
+            let line = AgentLine {
+                value: &value,
+                agent_id,
+                line_type,
+                usage,
+            };
+            self.process_agent_line(&line, &mut on_event);
+            return;
+        }
+
+        if state.files.workspace.is_none() {
+            state.files.workspace = value.get("cwd").and_then(|v| v.as_str()).map(String::from);
+        }
+
+        // Process todos from user messages. The compact summary doesn't
+        // carry the list over, so it would wipe it
+        if let Some(todos) = value.get("todos").and_then(|v| v.as_array()).filter(|_| !is_compact_summary) {
+            let todo_items: Vec<TodoItem> = todos
+                .iter()
+                .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                .collect();
+            update_todos(&mut state.todos, &todo_items);
+            on_event(todos_event(&state.todos));
+        }
+
+        // Process message content
+        if let Some(content) = value.get("message").and_then(|m| m.get("content")).and_then(|c| c.as_array()) {
+            for block in content {
+                let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                match block_type {
+                    "tool_use" => {
+                        let id = block.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                        let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                        let input = block.get("input");
+
+                        if id.is_empty() || name.is_empty() {
+                            continue;
+                        }
+
+                        // Handle TodoWrite
+                        if name == "TodoWrite" {
+                            if let Some(input) = input {
+                                if let Some(todos_arr) = input.get("todos").and_then(|v| v.as_array()) {
+                                    let todo_items: Vec<TodoItem> = todos_arr
+                                        .iter()
+                                        .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                                        .collect();
+                                    update_todos(&mut state.todos, &todo_items);
+                                    on_event(todos_event(&state.todos));
+                                }
+                            }
+                        }
+
+                        // Handle Task (agents)
+                        if name == "Task" {
+                            if let Some(input) = input {
+                                let agent_type = input
+                                    .get("subagent_type")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or("agent");
+
+                                agent_starts.insert(
+                                    id.to_string(),
+                                    AgentEntry {
+                                        agent_type: agent_type.to_string(),
+                                        status: Status::Running,
+                                        start_time: timestamp.clone(),
+                                        end_time: None,
+                                        start_turn: self.current_turn,
+                                        agent_id: None,
+                                        activity: AgentActivity::default(),
+                                    },
+                                );
+                                if state.agent_log.len() == AGENT_LOG_LIMIT {
+                                    state.agent_log.remove(0);
+                                }
+                                state.agent_log.push(AgentRun {
+                                    id: id.to_string(),
+                                    agent_type: agent_type.to_string(),
+                                    status: Status::Running,
+                                    start_time: timestamp.clone(),
+                                    end_time: None,
+                                });
+                                if let Some(prompt) = input.get("prompt").and_then(|v| v.as_str()) {
+                                    self.agent_prompts.insert(id.to_string(), prompt.to_string());
+                                }
+                                on_event(Event::AgentStarted {
+                                    id: id.to_string(),
+                                    agent_type: agent_type.to_string(),
+                                    timestamp: timestamp.clone(),
+                                });
+                            }
+                        } else if name == "Skill" {
+                            // Handle Skill invocations
+                            // Use skill name as key to deduplicate (only show most recent per skill)
+                            if let Some(input) = input {
+                                let skill_name = input
+                                    .get("skill")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or("skill");
+
+                                // Remove any previous entry for this skill name
+                                skill_starts.retain(|_, entry| entry.name != skill_name);
+
+                                skill_starts.insert(
+                                    id.to_string(),
+                                    SkillEntry {
+                                        name: skill_name.to_string(),
+                                        status: Status::Running,
+                                    },
+                                );
+                                on_event(Event::SkillStarted {
+                                    id: id.to_string(),
+                                    name: skill_name.to_string(),
+                                });
+                            }
+                        } else {
+                            if let Some(call) = job_call(name, input, &timestamp) {
+                                job_calls.insert(id.to_string(), call);
+                            }
+                            if let Some(path) = file_path(name, input) {
+                                self.file_calls.insert(id.to_string(), FileCall::new(name, path, input));
+                            }
+
+                            // Regular tool
+                            let target = extract_target(name, input);
+                            tool_starts.insert(
+                                id.to_string(),
+                                RunningTool {
+                                    name: name.to_string(),
+                                    target: target.clone(),
+                                    start_time: timestamp.clone(),
+                                },
+                            );
+                            on_event(Event::ToolStarted {
+                                id: id.to_string(),
+                                name: name.to_string(),
+                                target,
+                                path: file_path(name, input).map(String::from),
+                                timestamp: timestamp.clone(),
+                            });
+                        }
+                    }
+                    "tool_result" => {
+                        let tool_use_id = block
+                            .get("tool_use_id")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("");
+                        let is_error = block.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
+
+                        if tool_use_id.is_empty() {
+                            continue;
+                        }
+
+                        if let Some(call) = self.file_calls.remove(tool_use_id).filter(|_| !is_error) {
+                            let details = value.get("toolUseResult");
+                            state.diff.record(&call.path, call.diff(details));
+                            state.files.record(call, details);
+                        }
+
+                        if let Some(call) = job_calls.remove(tool_use_id) {
+                            let result = JobResult {
+                                tool_use_id,
+                                text: &result_text(block),
+                                details: value.get("toolUseResult"),
+                                is_error,
+                                timestamp: &timestamp,
+                            };
+                            apply_job_result(call, &result, &mut state.jobs, &mut on_event);
+                        }
+
+                        // Check if it's an agent
+                        if let Some(agent) = agent_starts.get_mut(tool_use_id) {
+                            agent.status = if is_error {
+                                Status::Error
+                            } else {
+                                Status::Completed
+                            };
+                            agent.end_time = timestamp.clone();
+                            if let Some(run) = state.agent_log.iter_mut().rev().find(|r| r.id == tool_use_id) {
+                                run.status = agent.status.clone();
+                                run.end_time = timestamp.clone();
+                            }
+
+                            // The result names the sidechain, in case no line
+                            // of it was matched while running
+                            let result_agent_id = value
+                                .get("toolUseResult")
+                                .and_then(|r| r.get("agentId"))
+                                .and_then(|v| v.as_str());
+                            if let (None, Some(agent_id)) = (&agent.agent_id, result_agent_id) {
+                                agent.agent_id = Some(agent_id.to_string());
+                                self.agent_ids.insert(agent_id.to_string(), tool_use_id.to_string());
+                            }
+                            self.agent_prompts.remove(tool_use_id);
+                            self.agent_tools.retain(|(_, task, _)| task != tool_use_id);
+
+                            on_event(Event::AgentFinished {
+                                id: tool_use_id.to_string(),
+                                agent_type: agent.agent_type.clone(),
+                                status: agent.status.clone(),
+                                timestamp: timestamp.clone(),
+                            });
+                            continue;
+                        }
+
+                        // An agent dropped from the statusline by the turn
+                        // reset, whose result came in turns later
+                        let late_agent = state
+                            .agent_log
+                            .iter_mut()
+                            .rev()
+                            .find(|r| r.id == tool_use_id && r.status == Status::Running);
+                        if let Some(run) = late_agent {
+                            run.status = if is_error { Status::Error } else { Status::Completed };
+                            run.end_time = timestamp.clone();
+                            on_event(Event::AgentFinished {
+                                id: tool_use_id.to_string(),
+                                agent_type: run.agent_type.clone(),
+                                status: run.status.clone(),
+                                timestamp: timestamp.clone(),
+                            });
+                            continue;
+                        }
+
+                        // Check if it's a skill
+                        if let Some(skill) = skill_starts.get_mut(tool_use_id) {
+                            skill.status = if is_error {
+                                Status::Error
+                            } else {
+                                Status::Completed
+                            };
+                            on_event(Event::SkillFinished {
+                                id: tool_use_id.to_string(),
+                                name: skill.name.clone(),
+                                status: skill.status.clone(),
+                            });
+                            continue;
+                        }
+
+                        // Regular tool - move from running to completed
+                        if let Some(RunningTool { name, target, start_time }) = tool_starts.remove(tool_use_id) {
+                            if is_error {
+                                *state.tools.errors.entry(name.clone()).or_insert(0) += 1;
+                                state.tools.last_error = Some(ToolError {
+                                    tool: name.clone(),
+                                    message: error_message(&result_text(block)),
+                                    timestamp: timestamp.clone(),
+                                });
+                            } else {
+                                *state.tools.completed.entry(name.clone()).or_insert(0) += 1;
+                            }
+                            let start_ms = start_time.as_deref().and_then(parse_timestamp);
+                            let end_ms = timestamp.as_deref().and_then(parse_timestamp);
+                            if let (Some(start_ms), Some(end_ms)) = (start_ms, end_ms) {
+                                let elapsed = end_ms.saturating_sub(start_ms);
+                                let duration = state.tools.durations.entry(name.clone()).or_default();
+                                duration.total_ms += elapsed;
+                                duration.max_ms = duration.max_ms.max(elapsed);
+                            }
+                            if state.tools.log.len() == TOOL_LOG_LIMIT {
+                                state.tools.log.remove(0);
+                            }
+                            state.tools.log.push(ToolCall {
+                                name: name.clone(),
+                                target,
+                                start_time,
+                                end_time: timestamp.clone(),
+                                is_error,
+                            });
+                            on_event(Event::ToolFinished {
+                                id: tool_use_id.to_string(),
+                                name,
+                                is_error,
+                                timestamp: timestamp.clone(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.update_session(&value, line_type, timestamp, &mut on_event);
+    }
+
+    /// Classify the session from a top-level user or assistant line.
+    fn update_session(
+        &mut self,
+        value: &Value,
+        line_type: &str,
+        timestamp: Option<String>,
+        on_event: &mut impl FnMut(Event),
+    ) {
+        let is_meta = value.get("isMeta").and_then(|v| v.as_bool()).unwrap_or(false)
+            || value.get("isCompactSummary").and_then(|v| v.as_bool()).unwrap_or(false);
+        if !matches!(line_type, "user" | "assistant") || is_meta {
+            return;
+        }
+
+        let message = value.get("message");
+        let content = message.and_then(|m| m.get("content"));
+        let text = match content {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Array(blocks)) => blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        };
+        let has_block = |kind: &str| {
+            content
+                .and_then(|c| c.as_array())
+                .is_some_and(|blocks| blocks.iter().any(|b| b.get("type").and_then(|t| t.as_str()) == Some(kind)))
+        };
+
+        let mut error = None;
+        let new_state = if line_type == "user" {
+            if text.contains("[Request interrupted by user") {
+                // Cancelled tools never get a result. Background shells
+                // keep running, so jobs are left alone.
+                self.tool_starts.clear();
+                self.skill_starts.retain(|_, skill| skill.status != Status::Running);
+                self.file_calls.clear();
+                let cancelled: Vec<String> = self
+                    .agent_starts
+                    .iter()
+                    .filter(|(_, agent)| agent.status == Status::Running)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in cancelled {
+                    let Some(agent) = self.agent_starts.remove(&id) else { continue };
+                    if let Some(run) = self.state.agent_log.iter_mut().rev().find(|r| r.id == id) {
+                        run.status = Status::Error;
+                        run.end_time = timestamp.clone();
+                    }
+                    self.agent_ids.retain(|_, task| *task != id);
+                    self.agent_prompts.remove(&id);
+                    self.agent_tools.retain(|(_, task, _)| *task != id);
+                    on_event(Event::AgentFinished {
+                        id,
+                        agent_type: agent.agent_type,
+                        status: Status::Error,
+                        timestamp: timestamp.clone(),
+                    });
+                }
+                SessionState::Interrupted
+            } else {
+                SessionState::Working
+            }
+        } else if value.get("isApiErrorMessage").and_then(|v| v.as_bool()) == Some(true) {
+            error = text.lines().map(str::trim).find(|l| !l.is_empty()).map(|l| truncate(l, 120));
+            SessionState::ApiError
+        } else {
+            let stop_reason = message.and_then(|m| m.get("stop_reason")).and_then(|v| v.as_str());
+            // Streamed blocks carry `stop_reason: null` mid-turn, so only an
+            // explicit stop finishes the turn
+            let finished = matches!(stop_reason, Some("end_turn") | Some("stop_sequence"));
+            if has_block("tool_use") || !finished {
+                SessionState::Working
+            } else {
+                SessionState::Idle
+            }
+        };
+
+        let session = &mut self.state.session;
+        if timestamp.is_some() {
+            if session.started_at.is_none() {
+                session.started_at = timestamp.clone();
+            }
+            session.last_activity = timestamp;
+        }
+        session.error = error;
+        if session.state != new_state {
+            session.state = new_state;
+            on_event(Event::SessionStateChanged { state: new_state });
+        }
+    }
+
+    /// Record a sidechain line's tools, todos, and usage on the agent it
+    /// belongs to. Lines of agents we can't place are dropped.
+    fn process_agent_line(&mut self, line: &AgentLine, on_event: &mut impl FnMut(Event)) {
+        let Some(task) = self.link_agent(line) else {
+            return;
+        };
+        let Some(agent) = self.agent_starts.get_mut(&task) else {
+            return;
+        };
+        let activity = &mut agent.activity;
+        activity.usage.add(&line.usage);
+
+        if let Some(todos) = line.value.get("todos").and_then(|v| v.as_array()) {
+            let todo_items: Vec<TodoItem> = todos
+                .iter()
+                .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                .collect();
+            update_todos(&mut activity.todos, &todo_items);
+        }
+
+        let Some(content) = line
+            .value
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|c| c.as_array())
+        else {
+            return;
+        };
+
+        for block in content {
+            match block.get("type").and_then(|v| v.as_str()) {
+                Some("tool_use") => {
+                    let id = block.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                    let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                    let input = block.get("input");
+                    if id.is_empty() || name.is_empty() {
+                        continue;
+                    }
+
+                    if name == "TodoWrite" {
+                        if let Some(todos_arr) = input.and_then(|i| i.get("todos")).and_then(|v| v.as_array()) {
+                            let todo_items: Vec<TodoItem> = todos_arr
+                                .iter()
+                                .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                                .collect();
+                            update_todos(&mut activity.todos, &todo_items);
+                        }
+                    }
+
+                    let target = extract_target(name, input);
+                    let tool = RunningTool {
+                        name: name.to_string(),
+                        target: target.clone(),
+                        start_time: line.value.get("timestamp").and_then(|v| v.as_str()).map(String::from),
+                    };
+                    self.agent_tools.push((id.to_string(), task.clone(), tool));
+                    on_event(Event::AgentToolStarted {
+                        agent: task.clone(),
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        target,
+                    });
+                }
+                Some("tool_result") => {
+                    let id = block.get("tool_use_id").and_then(|v| v.as_str()).unwrap_or("");
+                    let Some(pos) = self.agent_tools.iter().position(|(tool_id, _, _)| tool_id == id) else {
+                        continue;
+                    };
+                    let (_, _, tool) = self.agent_tools.remove(pos);
+                    *activity.completed.entry(tool.name.clone()).or_insert(0) += 1;
+                    on_event(Event::AgentToolFinished {
+                        agent: task.clone(),
+                        id: id.to_string(),
+                        name: tool.name,
+                        is_error: block.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Task id of the agent writing `line`'s sidechain. New sidechains are
+    /// matched by an explicit parent id, then by their first message
+    /// repeating a Task prompt, then to the oldest unlinked running agent.
+    fn link_agent(&mut self, line: &AgentLine) -> Option<String> {
+        if let Some(task) = self.agent_ids.get(line.agent_id) {
+            return Some(task.clone());
+        }
+
+        let agent_starts = &self.agent_starts;
+        let by_parent = ["parentToolUseID", "parent_tool_use_id"]
+            .iter()
+            .filter_map(|key| line.value.get(*key).and_then(|v| v.as_str()))
+            .find(|id| agent_starts.contains_key(*id))
+            .map(String::from);
+
+        let prompt = (line.line_type == "user")
+            .then(|| line.value.get("message").and_then(|m| m.get("content")).and_then(|c| c.as_str()))
+            .flatten();
+        let by_prompt = || {
+            let prompt = prompt?;
+            self.agent_prompts
+                .iter()
+                .find(|(_, p)| p.as_str() == prompt)
+                .map(|(task, _)| task.clone())
+        };
+
+        let oldest_unlinked = || {
+            agent_starts
+                .iter()
+                .filter(|(_, a)| a.status == Status::Running && a.agent_id.is_none())
+                .min_by(|(id_a, a), (id_b, b)| (&a.start_time, id_a).cmp(&(&b.start_time, id_b)))
+                .map(|(task, _)| task.clone())
+        };
+
+        let task = by_parent.or_else(by_prompt).or_else(oldest_unlinked)?;
+        self.agent_prompts.remove(&task);
+        self.agent_ids.insert(line.agent_id.to_string(), task.clone());
+        if let Some(agent) = self.agent_starts.get_mut(&task) {
+            agent.agent_id = Some(line.agent_id.to_string());
+        }
+        Some(task)
+    }
+
+    /// State as of the lines processed so far, without consuming the parser.
+    pub fn snapshot(&self, config: &Config) -> TranscriptState {
+        self.clone().finish(config)
+    }
+
+    /// Final state, with per-segment caps from `config` applied.
+    pub fn finish(self, config: &Config) -> TranscriptState {
+        let mut state = self.state;
+
+        // Convert remaining tool_starts to running tools
+        state.tools.running = self.tool_starts.into_values().collect();
+
+        // Convert agents, with their sidechain tools still running
+        let mut agent_starts = self.agent_starts;
+        for (_, task, tool) in self.agent_tools {
+            if let Some(agent) = agent_starts.get_mut(&task) {
+                agent.activity.running.push(tool);
+            }
+        }
+        state.agents = agent_starts.into_values().collect();
+
+        // Convert skills
+        state.skills = self.skill_starts.into_values().collect();
+
+        // Limit to recent entries
+        let segments = &config.segments;
+        if state.tools.running.len() > segments.tools.max_running {
+            let len = state.tools.running.len();
+            state.tools.running = state.tools.running.split_off(len - segments.tools.max_running);
+        }
+        if state.agents.len() > segments.agents.max {
+            let len = state.agents.len();
+            state.agents = state.agents.split_off(len - segments.agents.max);
+        }
+        if state.jobs.len() > segments.jobs.max {
+            let len = state.jobs.len();
+            state.jobs = state.jobs.split_off(len - segments.jobs.max);
+        }
+        if state.skills.len() > segments.skills.max {
+            let len = state.skills.len();
+            state.skills = state.skills.split_off(len - segments.skills.max);
+        }
+
+        state
+    }
+}
+
+/// Parse the whole transcript from the beginning. A missing or unreadable
+/// file yields an empty state.
+pub fn parse_transcript(path: &Path, config: &Config) -> TranscriptState {
+    match File::open(path) {
+        Ok(file) => parse_reader(BufReader::new(file), config),
+        Err(_) => TranscriptParser::default().finish(config),
+    }
+}
+
+/// Parse transcript lines from any reader, e.g. stdin or an in-memory buffer.
+pub fn parse_reader(reader: impl BufRead, config: &Config) -> TranscriptState {
+    let mut parser = TranscriptParser::default();
+
+    for line in reader.lines() {
+        match line {
+            Ok(l) => parser.process_line(&l),
+            Err(_) => continue,
+        }
+    }
+
+    parser.finish(config)
+}
+
+/// Parse the transcript, resuming from the checkpoint in `cache_dir` when it
+/// still matches the file, and persist a new checkpoint afterwards.
+pub fn parse_transcript_incremental(path: &Path, cache_dir: &Path, config: &Config) -> TranscriptState {
+    let mut file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return TranscriptParser::default().finish(config),
+    };
+
+    let (mut parser, mut offset) = match checkpoint::load(cache_dir, path, &mut file) {
+        Some(cp) => (cp.parser, cp.offset),
+        None => (TranscriptParser::default(), 0),
+    };
+
+    if file.seek(SeekFrom::Start(offset)).is_err() {
+        parser = TranscriptParser::default();
+        offset = 0;
+        let _ = file.seek(SeekFrom::Start(0));
+    }
+
+    let start_offset = offset;
+    let mut reader = BufReader::new(&mut file);
+    let mut buf = Vec::new();
+    let mut partial: Option<String> = None;
+
+    loop {
+        buf.clear();
+        let n = match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(_) => break,
+        };
+
+        // A line without its newline may still be mid-write: parse it for
+        // this render but leave it out of the checkpoint.
+        if buf.last() != Some(&b'\n') {
+            partial = Some(String::from_utf8_lossy(&buf).into_owned());
+            break;
+        }
+
+        if let Ok(line) = std::str::from_utf8(&buf) {
+            parser.process_line(line);
+        }
+        offset += n as u64;
+    }
+    drop(reader);
+
+    if offset != start_offset {
+        checkpoint::save(cache_dir, path, &mut file, offset, &parser);
+    }
+
+    match partial {
+        Some(line) => {
+            let mut parser = parser;
+            parser.process_line(&line);
+            parser.finish(config)
+        }
+        None => parser.finish(config),
+    }
+}
+
+/// Follows a transcript that is still being written, parsing only the
+/// lines appended since the last [`update`](Self::update). Unlike
+/// [`parse_transcript_incremental`], the parser stays in memory.
+#[derive(Debug, Default)]
+pub struct TranscriptFollower {
+    parser: TranscriptParser,
+    offset: u64,
+    /// Trailing line without its newline yet
+    partial: Option<String>,
+}
+
+impl TranscriptFollower {
+    /// Read what was appended. Returns whether anything changed; a file that
+    /// shrank is parsed again from the start.
+    pub fn update(&mut self, path: &Path) -> std::io::Result<bool> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            *self = TranscriptFollower::default();
+        }
+        let partial_len = self.partial.as_ref().map_or(0, |p| p.len() as u64);
+        if len == self.offset + partial_len {
+            return Ok(false);
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+        self.partial = None;
+
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)?;
+            if n == 0 {
+                break;
+            }
+            if buf.last() != Some(&b'\n') {
+                self.partial = Some(String::from_utf8_lossy(&buf).into_owned());
+                break;
+            }
+            if let Ok(line) = std::str::from_utf8(&buf) {
+                self.parser.process_line(line);
+            }
+            self.offset += n as u64;
+        }
+
+        Ok(true)
+    }
+
+    /// Current state, including a trailing line still being written.
+    pub fn state(&self, config: &Config) -> TranscriptState {
+        let mut parser = self.parser.clone();
+        if let Some(line) = &self.partial {
+            parser.process_line(line);
+        }
+        parser.finish(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, TURN};
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_incremental_resumes_from_checkpoint() {
+        let dir = temp_dir("resume");
+        let transcript = dir.join("t.jsonl");
+        let cache = dir.join("cache");
+        let (head, tail) = TURN.split_at(TURN.find("{\"type\":\"user\",\"message\":{\"content\":[").unwrap());
+
+        fs::write(&transcript, head).unwrap();
+        parse_transcript_incremental(&transcript, &cache, &Config::default());
+        let mut f = fs::OpenOptions::new().append(true).open(&transcript).unwrap();
+        f.write_all(tail.as_bytes()).unwrap();
+
+        let state = parse_transcript_incremental(&transcript, &cache, &Config::default());
+        assert_eq!(state.tools.completed.get("Read"), Some(&1));
+        assert_eq!(state.tools.running.len(), 1);
+        assert_eq!(state.tools.running[0].name, "Bash");
+    }
+
+    #[test]
+    fn test_incremental_falls_back_on_truncation() {
+        let dir = temp_dir("truncate");
+        let transcript = dir.join("t.jsonl");
+        let cache = dir.join("cache");
+
+        fs::write(&transcript, TURN).unwrap();
+        parse_transcript_incremental(&transcript, &cache, &Config::default());
+
+        let short = TURN.lines().next().unwrap();
+        fs::write(&transcript, format!("{short}\n")).unwrap();
+        let state = parse_transcript_incremental(&transcript, &cache, &Config::default());
+        assert!(state.tools.completed.is_empty());
+        assert!(state.tools.running.is_empty());
+    }
+
+    #[test]
+    fn test_follower_reads_appended_lines() {
+        let dir = temp_dir("follow");
+        let transcript = dir.join("t.jsonl");
+        let config = Config::default();
+        let (head, tail) = TURN.split_at(TURN.find("{\"type\":\"user\",\"message\":{\"content\":[").unwrap());
+        let (partial, rest) = tail.split_at(20);
+
+        fs::write(&transcript, head).unwrap();
+        let mut follower = TranscriptFollower::default();
+        assert!(follower.update(&transcript).unwrap());
+        assert!(!follower.update(&transcript).unwrap());
+        assert_eq!(follower.state(&config).tools.running[0].name, "Read");
+
+        let mut f = fs::OpenOptions::new().append(true).open(&transcript).unwrap();
+        f.write_all(partial.as_bytes()).unwrap();
+        assert!(follower.update(&transcript).unwrap());
+        f.write_all(rest.as_bytes()).unwrap();
+        assert!(follower.update(&transcript).unwrap());
+        let state = follower.state(&config);
+        assert_eq!(state.tools.completed.get("Read"), Some(&1));
+        assert_eq!(state.tools.running[0].name, "Bash");
+
+        fs::write(&transcript, head).unwrap();
+        assert!(follower.update(&transcript).unwrap());
+        assert!(follower.state(&config).tools.completed.is_empty());
+    }
+
+    #[test]
+    fn test_process_line_emits_events() {
+        let mut parser = TranscriptParser::default();
+        let mut events = Vec::new();
+        for line in TURN.lines() {
+            parser.process_line_with(line, |e| events.push(e));
+        }
+        assert!(matches!(events[0], Event::SessionStateChanged { state: SessionState::Working }));
+        assert!(matches!(events[1], Event::TurnStarted { turn: 1 }));
+        assert!(matches!(&events[2], Event::ToolStarted { name, .. } if name == "Read"));
+        assert!(matches!(&events[3], Event::ToolFinished { name, is_error: false, .. } if name == "Read"));
+        assert!(matches!(&events[4], Event::ToolStarted { target: Some(t), .. } if t == "cargo test"));
+    }
+
+    #[test]
+    fn test_tool_durations() {
+        let lines = [
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:00.000Z","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"make"}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-15T10:30:04.500Z","message":{"content":[{"type":"tool_result","tool_use_id":"t1"}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:05.000Z","message":{"content":[{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"make test"}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-15T10:30:06.000Z","message":{"content":[{"type":"tool_result","tool_use_id":"t2"}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-15T10:30:07.000Z","message":{"content":[{"type":"tool_use","id":"t3","name":"Read","input":{"file_path":"a.rs"}}]}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let state = parser.finish(&Config::default());
+
+        assert_eq!(state.tools.durations["Bash"], ToolDuration { total_ms: 5_500, max_ms: 4_500 });
+        assert_eq!(state.tools.running[0].start_time.as_deref(), Some("2025-01-15T10:30:07.000Z"));
+    }
+
+    #[test]
+    fn test_usage_deduplicated_by_message_id() {
+        let lines = [
+            r#"{"type":"user","message":{"content":"first"}}"#,
+            r#"{"type":"assistant","message":{"id":"m1","usage":{"input_tokens":10,"cache_read_input_tokens":90,"output_tokens":5},"content":[{"type":"text","text":"a"}]}}"#,
+            r#"{"type":"assistant","message":{"id":"m1","usage":{"input_tokens":10,"cache_read_input_tokens":90,"output_tokens":20},"content":[{"type":"tool_use","id":"t1","name":"Read","input":{}}]}}"#,
+            r#"{"type":"user","message":{"content":"second"}}"#,
+            r#"{"type":"assistant","message":{"id":"m2","usage":{"input_tokens":4,"cache_creation_input_tokens":16,"output_tokens":2},"content":[]}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let usage = parser.finish(&Config::default()).usage;
+
+        assert_eq!(usage.turn.total(), 22);
+        assert_eq!(usage.session.output_tokens, 22);
+        assert_eq!(usage.session.total(), 142);
+        assert_eq!(usage.session.cache_hit_ratio(), Some(0.75));
+    }
+
+    #[test]
+    fn test_background_jobs_survive_turns() {
+        let lines = [
+            r#"{"type":"user","message":{"content":"start servers"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"npm run dev","run_in_background":true}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"Command running in background with ID: bash_1"}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"cargo build","run_in_background":true}}]}}"#,
+            r#"{"type":"user","toolUseResult":{"backgroundTaskId":"bash_2"},"message":{"content":[{"type":"tool_result","tool_use_id":"t2","content":[{"type":"text","text":"started"}]}]}}"#,
+            r#"{"type":"user","message":{"content":"check on them"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t3","name":"BashOutput","input":{"bash_id":"bash_2"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t3","content":"<status>completed</status>\n\n<exit_code>101</exit_code>"}]}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let jobs = parser.snapshot(&Config::default()).jobs;
+        assert_eq!(jobs.len(), 2);
+        assert_eq!((jobs[0].id.as_str(), jobs[0].status), ("bash_1", JobStatus::Running));
+        assert_eq!((jobs[1].id.as_str(), jobs[1].status, jobs[1].exit_code), ("bash_2", JobStatus::Failed, Some(101)));
+
+        for line in [
+            r#"{"type":"user","message":{"content":"stop the server"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t4","name":"KillShell","input":{"shell_id":"bash_1"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t4","content":"Successfully killed shell: bash_1"}]}}"#,
+        ] {
+            parser.process_line(line);
+        }
+        let jobs = parser.finish(&Config::default()).jobs;
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, JobStatus::Killed);
+    }
+
+    #[test]
+    fn test_agent_sidechain_activity() {
+        let lines = [
+            r#"{"type":"user","message":{"content":"find the auth code"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"task1","name":"Task","input":{"subagent_type":"Explore","prompt":"Find auth"}}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"task2","name":"Task","input":{"subagent_type":"Plan","prompt":"Plan it"}}]}}"#,
+            r#"{"type":"user","agentId":"a2","isSidechain":true,"message":{"content":"Plan it"}}"#,
+            r#"{"type":"user","agentId":"a1","isSidechain":true,"message":{"content":"Find auth"}}"#,
+            r#"{"type":"assistant","agentId":"a1","isSidechain":true,"message":{"id":"m1","usage":{"output_tokens":7},"content":[{"type":"tool_use","id":"s1","name":"Read","input":{"file_path":"lib.rs"}}]}}"#,
+            r#"{"type":"user","agentId":"a1","isSidechain":true,"message":{"content":[{"type":"tool_result","tool_use_id":"s1"}]}}"#,
+            r#"{"type":"assistant","agentId":"a1","isSidechain":true,"message":{"content":[{"type":"tool_use","id":"s2","name":"Grep","input":{"pattern":"auth"}}]}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let state = parser.finish(&Config::default());
+
+        // Sidechain tools belong to the agent, not the main turn
+        assert!(state.tools.running.is_empty());
+        assert!(state.tools.completed.is_empty());
+        assert_eq!(state.usage.session.output_tokens, 7);
+
+        let explore = state.agents.iter().find(|a| a.agent_type == "Explore").unwrap();
+        assert_eq!(explore.agent_id.as_deref(), Some("a1"));
+        assert_eq!(explore.activity.completed.get("Read"), Some(&1));
+        assert_eq!(explore.activity.usage.output_tokens, 7);
+        let current = explore.activity.current().unwrap();
+        assert_eq!((current.name.as_str(), current.target.as_deref()), ("Grep", Some("auth")));
+
+        let plan = state.agents.iter().find(|a| a.agent_type == "Plan").unwrap();
+        assert_eq!(plan.agent_id.as_deref(), Some("a2"));
+        assert!(plan.activity.current().is_none());
+    }
+
+    #[test]
+    fn test_mcp_tool_names_and_targets() {
+        assert_eq!(parse_mcp_name("mcp__github__create_issue"), Some(("github", "create_issue")));
+        assert_eq!(parse_mcp_name("mcp__claude_ai_Linear__list_issues"), Some(("claude_ai_Linear", "list_issues")));
+        assert_eq!(parse_mcp_name("mcp__broken"), None);
+        assert_eq!(parse_mcp_name("Read"), None);
+
+        let input: Value = serde_json::from_str(r#"{"owner":"rust-lang","repo":"rust","issue_number":42}"#).unwrap();
+        assert_eq!(extract_target("mcp__github__get_issue", Some(&input)).as_deref(), Some("rust"));
+        let input: Value = serde_json::from_str(r#"{"issue_number":42}"#).unwrap();
+        assert_eq!(extract_target("mcp__github__get_issue", Some(&input)).as_deref(), Some("#42"));
+    }
+
+    #[test]
+    fn test_tool_errors_tracked_separately() {
+        let lines = [
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"a.rs"}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-15T10:30:00Z","message":{"content":[{"type":"tool_result","tool_use_id":"t1","is_error":true,"content":"<tool_use_error>String to replace not found in file.\nString: foo</tool_use_error>"}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"a.rs"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2"}]}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let tools = parser.finish(&Config::default()).tools;
+
+        assert_eq!(tools.completed.get("Edit"), Some(&1));
+        assert_eq!(tools.errors.get("Edit"), Some(&1));
+        let last = tools.last_error.unwrap();
+        assert_eq!(last.tool, "Edit");
+        assert_eq!(last.message, "String to replace not found in file.");
+        assert_eq!(last.timestamp.as_deref(), Some("2025-01-15T10:30:00Z"));
+    }
+
+    #[test]
+    fn test_compaction_keeps_turn_state() {
+        let lines = [
+            r#"{"type":"user","message":{"content":"refactor auth"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"TodoWrite","input":{"todos":[{"status":"completed","activeForm":"Reading"},{"status":"in_progress","activeForm":"Refactoring"}]}}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"b1","name":"Bash","input":{"command":"npm run dev","run_in_background":true}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"},{"type":"tool_result","tool_use_id":"b1","content":"Command running in background with ID: bash_1"}]}}"#,
+            r#"{"type":"system","subtype":"compact_boundary","timestamp":"2025-01-15T10:30:00Z","compactMetadata":{"trigger":"auto","preTokens":155000}}"#,
+            r#"{"type":"user","isCompactSummary":true,"todos":[],"message":{"content":"This session is being continued from a previous conversation..."}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Read","input":{"file_path":"auth.rs"}}]}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        let mut events = Vec::new();
+        for line in lines {
+            parser.process_line_with(line, |e| events.push(e));
+        }
+        let state = parser.snapshot(&Config::default());
+
+        assert_eq!(state.compaction.count, 1);
+        assert_eq!(state.compaction.last_trigger.as_deref(), Some("auto"));
+        assert_eq!(state.compaction.last_pre_tokens, Some(155000));
+        assert!(events.iter().any(|e| matches!(e, Event::Compacted { count: 1, .. })));
+        assert_eq!(events.iter().filter(|e| matches!(e, Event::TurnStarted { .. })).count(), 1);
+        assert_eq!((state.todos.done, state.todos.total), (1, 2));
+        assert_eq!(state.jobs.len(), 1);
+        assert_eq!(state.tools.completed.get("TodoWrite"), Some(&1));
+        assert_eq!(state.tools.running.len(), 1);
+    }
+
+    #[test]
+    fn test_files_touched_per_turn_and_session() {
+        let lines = [
+            r#"{"type":"user","cwd":"/repo","message":{"content":"fix login"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/repo/src/auth.rs"}},{"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"/repo/src/auth.rs"}},{"type":"tool_use","id":"t3","name":"Edit","input":{"file_path":"/repo/src/main.rs"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"},{"type":"tool_result","tool_use_id":"t2"},{"type":"tool_result","tool_use_id":"t3","is_error":true}]}}"#,
+            r#"{"type":"user","message":{"content":"add a test"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t4","name":"Write","input":{"file_path":"/repo/tests/auth.rs","content":""}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t4"}]},"toolUseResult":{"type":"create","filePath":"/repo/tests/auth.rs"}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let files = parser.snapshot(&Config::default()).files;
+
+        assert_eq!(files.workspace.as_deref(), Some("/repo"));
+        assert_eq!(files.turn.len(), 1);
+        assert!(files.turn.created.contains("/repo/tests/auth.rs"));
+        assert_eq!(files.session.len(), 2);
+        assert!(files.session.read.contains("/repo/src/auth.rs"));
+        assert!(files.session.modified.contains("/repo/src/auth.rs"));
+        assert!(!files.session.modified.contains("/repo/src/main.rs"));
+        assert_eq!(relative_path(files.last.as_deref().unwrap(), Some("/repo/")), "tests/auth.rs");
+        assert_eq!(relative_path("/other/x.rs", Some("/repo")), "/other/x.rs");
+    }
+
+    #[test]
+    fn test_diff_stats_from_inputs() {
+        assert_eq!(DiffStats::between("a\nb\nc\n", "a\nB\nB2\nc\n"), DiffStats { added: 2, removed: 1 });
+        assert_eq!(DiffStats::between("", "x\ny"), DiffStats { added: 2, removed: 0 });
+
+        let lines = [
+            r#"{"type":"user","cwd":"/repo","message":{"content":"fix login"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"/repo/a.rs","old_string":"fn a() {\n    1\n}","new_string":"fn a() {\n    2\n    3\n}"}}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"/repo/b.rs","old_string":"x","new_string":"y"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1"},{"type":"tool_result","tool_use_id":"t2","is_error":true}]}}"#,
+            r#"{"type":"user","message":{"content":"more"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t3","name":"MultiEdit","input":{"file_path":"/repo/a.rs","edits":[{"old_string":"2","new_string":""},{"old_string":"","new_string":"4\n5"}]}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t3"}]}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t4","name":"Write","input":{"file_path":"/repo/c.rs","content":"one\ntwo\nthree\n"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t4"}]},"toolUseResult":{"type":"update","originalFile":"one\n2\nthree\n"}}"#,
+        ];
+        let mut parser = TranscriptParser::default();
+        for line in lines {
+            parser.process_line(line);
+        }
+        let diff = parser.snapshot(&Config::default()).diff;
+
+        assert_eq!(diff.turn, DiffStats { added: 3, removed: 2 });
+        assert_eq!(diff.session, DiffStats { added: 5, removed: 3 });
+        assert_eq!(diff.by_file["/repo/a.rs"], DiffStats { added: 4, removed: 2 });
+        assert!(!diff.by_file.contains_key("/repo/b.rs"));
+    }
+
+    #[test]
+    fn test_truncate_path_long() {
+        let path = "testdir/dir1/dir2/dir3/dir4/dir5/longfilenametest.py";
+        let result = truncate_path(path, 30);
+        assert_eq!(result, ".../longfilenametest.py", "Path: {}, Result: {}", path, result);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::transcript::TranscriptFollower;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// ============================================================================
+// Watch Mode (`claude-status watch`)
+// ============================================================================
+
+/// How often to redraw without file changes, so elapsed times keep ticking.
+pub const TICK: Duration = Duration::from_secs(1);
+
+/// Poll interval where inotify isn't available.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// Make SIGINT and SIGTERM end [`follow`] instead of the process, so the
+/// caller gets to restore the terminal.
+pub fn catch_signals() {
+    #[cfg(unix)]
+    {
+        extern "C" fn on_signal(_: libc::c_int) {
+            STOP.store(true, Ordering::SeqCst);
+        }
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        // SAFETY: the handler only stores to an atomic
+        unsafe {
+            libc::signal(libc::SIGINT, handler);
+            libc::signal(libc::SIGTERM, handler);
+        }
+    }
+}
+
+/// Terminal columns of stdout, if it is a terminal.
+pub fn terminal_width() -> Option<usize> {
+    terminal_size().map(|(columns, _)| columns)
+}
+
+/// Terminal columns and rows of stdout, if it is a terminal.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    #[cfg(unix)]
+    {
+        // SAFETY: TIOCGWINSZ only writes the winsize struct
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if ok && size.ws_col > 0 && size.ws_row > 0 {
+            return Some((size.ws_col as usize, size.ws_row as usize));
+        }
+    }
+    None
+}
+
+/// Hidden cursor and no autowrap, so [`redraw_line`] replaces the whole line.
+pub const LINE_ENTER: &str = "\x1b[?25l\x1b[?7l";
+pub const LINE_LEAVE: &str = "\x1b[?7h\x1b[?25h";
+
+/// Replace the current line with `line`.
+pub fn redraw_line(out: &mut impl Write, line: &str) -> io::Result<()> {
+    write!(out, "\r\x1b[2K{}", line)?;
+    out.flush()
+}
+
+/// Alternate screen, hidden cursor, no autowrap.
+pub const FULL_SCREEN_ENTER: &str = "\x1b[?1049h\x1b[?25l\x1b[?7l";
+pub const FULL_SCREEN_LEAVE: &str = "\x1b[?7h\x1b[?25h\x1b[?1049l";
+
+/// Replace the screen contents with `lines`. Overwrites in place rather
+/// than clearing first, which flickers.
+pub fn redraw(out: &mut impl Write, lines: &[String]) -> io::Result<()> {
+    write!(out, "\x1b[H{}\x1b[K\x1b[J", lines.join("\x1b[K\r\n"))?;
+    out.flush()
+}
+
+/// Follow the transcript at `path`, calling `on_update` once at the start,
+/// after every change, and at least every `tick`. Returns when the file is
+/// removed or a signal caught by [`catch_signals`] arrives.
+pub fn follow(
+    path: &Path,
+    tick: Duration,
+    mut on_update: impl FnMut(&TranscriptFollower) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut follower = TranscriptFollower::default();
+    let mut waiter = Waiter::new(path);
+
+    while !STOP.load(Ordering::SeqCst) && path.exists() {
+        match follower.update(path) {
+            Ok(_) => {}
+            // Removed between the check and the read
+            Err(err) if err.kind() == io::ErrorKind::NotFound => break,
+            Err(err) => return Err(err),
+        }
+        on_update(&follower)?;
+        waiter.wait(tick);
+    }
+
+    Ok(())
+}
+
+/// Call `on_tick` right away and then every `tick`, until a signal caught
+/// by [`catch_signals`] arrives.
+pub fn repeat(tick: Duration, mut on_tick: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    while !STOP.load(Ordering::SeqCst) {
+        on_tick()?;
+        // Sleep in slices, since a signal doesn't cut a sleep short
+        let deadline = Instant::now() + tick;
+        while !STOP.load(Ordering::SeqCst) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            std::thread::sleep(left.min(POLL_INTERVAL));
+        }
+    }
+    Ok(())
+}
+
+/// Blocks until the file changes or a timeout passes.
+enum Waiter {
+    #[cfg(target_os = "linux")]
+    Inotify(std::os::fd::OwnedFd),
+    Poll,
+}
+
+impl Waiter {
+    fn new(path: &Path) -> Self {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = inotify_watch(path) {
+            return Waiter::Inotify(fd);
+        }
+        let _ = path;
+        Waiter::Poll
+    }
+
+    /// Wait up to `timeout`. Wakes early on a change (inotify) or a signal.
+    fn wait(&mut self, timeout: Duration) {
+        match self {
+            #[cfg(target_os = "linux")]
+            Waiter::Inotify(fd) => {
+                use std::os::fd::AsRawFd;
+                let mut pfd = libc::pollfd {
+                    fd: fd.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                // SAFETY: one valid pollfd; a signal makes poll return EINTR
+                let ready = unsafe { libc::poll(&mut pfd, 1, timeout.as_millis() as libc::c_int) };
+                if ready > 0 {
+                    // Drain the events; which ones arrived doesn't matter
+                    let mut buf = [0u8; 4096];
+                    // SAFETY: the fd is non-blocking and buf is large enough
+                    while unsafe { libc::read(pfd.fd, buf.as_mut_ptr().cast(), buf.len()) } > 0 {}
+                }
+            }
+            Waiter::Poll => std::thread::sleep(timeout.min(POLL_INTERVAL)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn inotify_watch(path: &Path) -> Option<std::os::fd::OwnedFd> {
+    use std::ffi::CString;
+    use std::os::fd::FromRawFd;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: plain syscalls; the fd is owned from here on
+    unsafe {
+        let fd = libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+        if fd < 0 {
+            return None;
+        }
+        let fd = std::os::fd::OwnedFd::from_raw_fd(fd);
+        let mask = libc::IN_MODIFY | libc::IN_ATTRIB | libc::IN_DELETE_SELF | libc::IN_MOVE_SELF;
+        if libc::inotify_add_watch(std::os::fd::AsRawFd::as_raw_fd(&fd), path.as_ptr(), mask) < 0 {
+            return None;
+        }
+        Some(fd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, TURN};
+    use std::fs;
+
+    #[test]
+    fn test_follow_ends_when_file_removed() {
+        let dir = temp_dir("watch");
+        let transcript = dir.join("t.jsonl");
+        fs::write(&transcript, TURN).unwrap();
+
+        let mut updates = 0;
+        follow(&transcript, Duration::from_millis(20), |follower| {
+            updates += 1;
+            assert_eq!(follower.state(&Default::default()).tools.running.len(), 1);
+            if updates == 3 {
+                fs::remove_file(&transcript)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(updates, 3);
+    }
+}
@@ -25,6 +25,13 @@ else
     echo -e "  ${YELLOW}!${NC} Binary not found (already removed?)"
 fi
 
+# Remove parse checkpoints (a custom CLAUDE_STATUS_CACHE_DIR is left alone)
+CACHE_DIR="${XDG_CACHE_HOME:-$HOME/.cache}/claude-status"
+if [ -d "$CACHE_DIR" ]; then
+    rm -rf "$CACHE_DIR"
+    echo -e "  ${GREEN}✓${NC} Removed $CACHE_DIR"
+fi
+
 # Note: statusline.sh lives in the repo, not copied to ~/.claude/
 # So we only need to remove the binary and settings
 